@@ -0,0 +1,155 @@
+use anchor_lang::prelude::*;
+use solana_bn254::prelude::{alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing};
+
+use crate::ErrorCode;
+
+// Sizes of EIP-197 encoded BN254 elements, as expected by the alt_bn128 syscalls
+pub const FIELD_ELEMENT_LEN: usize = 32;
+pub const G1_POINT_LEN: usize = 64;
+pub const G2_POINT_LEN: usize = 128;
+// A Groth16 proof is A (G1) || B (G2) || C (G1)
+pub const PROOF_LEN: usize = G1_POINT_LEN + G2_POINT_LEN + G1_POINT_LEN;
+// Upper bound on public inputs so a verifying key always fits in a transaction
+pub const MAX_PUBLIC_INPUTS: usize = 8;
+
+// BN254 base field modulus (big-endian), used to negate G1 points
+const BASE_FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
+];
+
+// BN254 scalar field modulus (big-endian); public inputs must be reduced below it
+const SCALAR_FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
+];
+
+// Groth16 verifying key with all points in EIP-197 (big-endian) encoding
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct Groth16VerifyingKey {
+    pub alpha_g1: [u8; G1_POINT_LEN],
+    pub beta_g2: [u8; G2_POINT_LEN],
+    pub gamma_g2: [u8; G2_POINT_LEN],
+    pub delta_g2: [u8; G2_POINT_LEN],
+    pub ic: Vec<[u8; G1_POINT_LEN]>, // One point per public input, plus the constant term
+}
+
+impl Groth16VerifyingKey {
+    pub fn num_public_inputs(&self) -> usize {
+        self.ic.len().saturating_sub(1)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            !self.ic.is_empty() && self.num_public_inputs() <= MAX_PUBLIC_INPUTS,
+            ErrorCode::InvalidVerifyingKey
+        );
+        Ok(())
+    }
+}
+
+// Check the Groth16 pairing equation
+//   e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1
+// where vk_x = ic[0] + sum(public_inputs[i] * ic[i + 1])
+pub fn verify(
+    verifying_key: &Groth16VerifyingKey,
+    proof: &[u8; PROOF_LEN],
+    public_inputs: &[[u8; FIELD_ELEMENT_LEN]],
+) -> Result<()> {
+    verifying_key.validate()?;
+    require!(
+        public_inputs.len() == verifying_key.num_public_inputs(),
+        ErrorCode::InvalidZKProof
+    );
+
+    let proof_a = &proof[..G1_POINT_LEN];
+    let proof_b = &proof[G1_POINT_LEN..G1_POINT_LEN + G2_POINT_LEN];
+    let proof_c = &proof[G1_POINT_LEN + G2_POINT_LEN..];
+
+    let vk_x = prepare_inputs(verifying_key, public_inputs)?;
+    let neg_a = negate_g1(proof_a)?;
+
+    let mut pairing_input = Vec::with_capacity(4 * (G1_POINT_LEN + G2_POINT_LEN));
+    pairing_input.extend_from_slice(&neg_a);
+    pairing_input.extend_from_slice(proof_b);
+    pairing_input.extend_from_slice(&verifying_key.alpha_g1);
+    pairing_input.extend_from_slice(&verifying_key.beta_g2);
+    pairing_input.extend_from_slice(&vk_x);
+    pairing_input.extend_from_slice(&verifying_key.gamma_g2);
+    pairing_input.extend_from_slice(proof_c);
+    pairing_input.extend_from_slice(&verifying_key.delta_g2);
+
+    let result = alt_bn128_pairing(&pairing_input).map_err(|_| error!(ErrorCode::InvalidZKProof))?;
+
+    // The syscall returns 1 as a big-endian 32-byte word when the product is the identity
+    let mut expected = [0u8; 32];
+    expected[31] = 1;
+    require!(result.as_slice() == expected, ErrorCode::InvalidZKProof);
+
+    Ok(())
+}
+
+// Fold the public inputs into the verifying key's linear combination
+fn prepare_inputs(
+    verifying_key: &Groth16VerifyingKey,
+    public_inputs: &[[u8; FIELD_ELEMENT_LEN]],
+) -> Result<[u8; G1_POINT_LEN]> {
+    let mut vk_x = verifying_key.ic[0];
+
+    for (input, point) in public_inputs.iter().zip(verifying_key.ic[1..].iter()) {
+        require!(
+            is_below(input, &SCALAR_FIELD_MODULUS),
+            ErrorCode::InvalidZKProof
+        );
+
+        let mut mul_input = [0u8; G1_POINT_LEN + FIELD_ELEMENT_LEN];
+        mul_input[..G1_POINT_LEN].copy_from_slice(point);
+        mul_input[G1_POINT_LEN..].copy_from_slice(input);
+        let product = alt_bn128_multiplication(&mul_input)
+            .map_err(|_| error!(ErrorCode::InvalidZKProof))?;
+
+        let mut add_input = [0u8; 2 * G1_POINT_LEN];
+        add_input[..G1_POINT_LEN].copy_from_slice(&vk_x);
+        add_input[G1_POINT_LEN..].copy_from_slice(&product);
+        let sum = alt_bn128_addition(&add_input).map_err(|_| error!(ErrorCode::InvalidZKProof))?;
+
+        vk_x = sum
+            .try_into()
+            .map_err(|_| error!(ErrorCode::InvalidZKProof))?;
+    }
+
+    Ok(vk_x)
+}
+
+// Negate a G1 point by mapping (x, y) to (x, p - y); the point at infinity is its own negation
+pub fn negate_g1(point: &[u8]) -> Result<[u8; G1_POINT_LEN]> {
+    require!(point.len() == G1_POINT_LEN, ErrorCode::InvalidZKProof);
+
+    let mut negated = [0u8; G1_POINT_LEN];
+    negated.copy_from_slice(point);
+
+    let y = &point[FIELD_ELEMENT_LEN..];
+    if y.iter().all(|&b| b == 0) {
+        return Ok(negated);
+    }
+    require!(is_below(y, &BASE_FIELD_MODULUS), ErrorCode::InvalidZKProof);
+
+    // Big-endian subtraction p - y; cannot underflow since y < p
+    let mut borrow = 0i16;
+    for i in (0..FIELD_ELEMENT_LEN).rev() {
+        let mut diff = BASE_FIELD_MODULUS[i] as i16 - y[i] as i16 - borrow;
+        borrow = 0;
+        if diff < 0 {
+            diff += 256;
+            borrow = 1;
+        }
+        negated[FIELD_ELEMENT_LEN + i] = diff as u8;
+    }
+
+    Ok(negated)
+}
+
+// Compare two big-endian integers of equal length
+fn is_below(value: &[u8], modulus: &[u8; 32]) -> bool {
+    value.len() == modulus.len() && value < modulus.as_slice()
+}
@@ -1,4 +1,15 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::system_program;
+use solana_program::compute_units::sol_remaining_compute_units;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+pub mod groth16;
+pub mod migration;
+
+use groth16::Groth16VerifyingKey;
+use migration::EncryptedComputeV0;
 
 // Define the program ID - in a real project, this would be generated by Anchor
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
@@ -8,523 +19,5299 @@ pub mod arcium_encrypted_compute {
     use super::*;
 
     // Initialize a new encrypted compute account
-    pub fn initialize_encrypted_compute(ctx: Context<InitializeEncryptedCompute>, data_hash: String) -> Result<()> {
-        // Validate data hash is not empty
-        require!(!data_hash.is_empty(), ErrorCode::InvalidData);
-        // Limit the length of the hash to prevent excessive storage costs
-        require!(data_hash.len() <= 256, ErrorCode::DataTooLong);
+    pub fn initialize_encrypted_compute(ctx: Context<InitializeEncryptedCompute>, data_hash: [u8; 32],
+                                        hash_algorithm: DigestAlgorithm) -> Result<()> {
+        // Validate data hash is set
+        require!(data_hash != [0u8; 32], ErrorCode::InvalidData);
 
         let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+        encrypted_compute.version = ENCRYPTED_COMPUTE_VERSION;
         encrypted_compute.authority = ctx.accounts.user.key();
+        encrypted_compute.creator = ctx.accounts.user.key();
+        encrypted_compute.pending_authority = None;
         encrypted_compute.data_hash = data_hash;
-        encrypted_compute.encrypted_data = String::new();
+        encrypted_compute.hash_algorithm = hash_algorithm;
+        encrypted_compute.legacy_seed = Vec::new();
+        encrypted_compute.encrypted_data = Vec::new();
+        encrypted_compute.ciphertext_metadata = None;
+        encrypted_compute.storage = CiphertextStorage::Inline;
+        encrypted_compute.ciphertext_generation = 0;
+        encrypted_compute.ciphertext_upload_open = false;
+        encrypted_compute.pending_chunks = 0;
         encrypted_compute.status = EncryptedComputeStatus::Initialized;
         encrypted_compute.created_at = Clock::get()?.unix_timestamp;
         encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
         encrypted_compute.time_lock_expiration = 0;  // No time lock by default
         encrypted_compute.is_locked = false;
         encrypted_compute.unlock_conditions_met = false;
+        encrypted_compute.time_lock_committed = false;
+        encrypted_compute.lock_guards = LOCK_GUARD_DEFAULT;
+        encrypted_compute.unlock_policy = None;
+        encrypted_compute.delegates = Vec::new();
+        encrypted_compute.multisig = None;
+        encrypted_compute.multisig_nonce = 0;
+        encrypted_compute.multisig_proposals_created = 0;
+        encrypted_compute.time_locks_applied = 0;
+        encrypted_compute.computations_queued = 0;
+        encrypted_compute.computations_completed = 0;
+        encrypted_compute.proofs_stored = 0;
+        encrypted_compute.proofs_verified = 0;
+        encrypted_compute.audit_trails_created = 0;
+        encrypted_compute.audit_events_logged = 0;
+        encrypted_compute.disclosures_verified = 0;
+        encrypted_compute.credentials_issued = 0;
         
         Ok(())
     }
 
     // Update encrypted data
-    pub fn update_encrypted_data(ctx: Context<UpdateEncryptedData>, encrypted_data: String) -> Result<()> {
-        // Validate encrypted data is not empty
-        require!(!encrypted_data.is_empty(), ErrorCode::InvalidData);
-        // Limit the length to prevent excessive storage costs
-        require!(encrypted_data.len() <= 1024, ErrorCode::DataTooLong);
-
+    pub fn update_encrypted_data(ctx: Context<UpdateEncryptedData>, encrypted_data: Vec<u8>,
+                                 metadata: CiphertextMetadata) -> Result<()> {
         let encrypted_compute = &mut ctx.accounts.encrypted_compute;
         
-        // Only the authority can update the data
-        require!(
-            *ctx.accounts.user.key == encrypted_compute.authority,
-            ErrorCode::Unauthorized
-        );
+        // Only the authority or a delegate with update permission can update the data
+        encrypted_compute.require_permission(ctx.accounts.user.key, PERMISSION_UPDATE)?;
 
-        encrypted_compute.encrypted_data = encrypted_data;
-        encrypted_compute.status = EncryptedComputeStatus::Updated;
-        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+        // Multisig-governed accounts only update through a proposal
+        encrypted_compute.require_single_authority()?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        encrypted_compute.set_encrypted_data(encrypted_data, metadata, current_time)?;
+        encrypted_compute.updated_at = current_time;
         
         Ok(())
     }
 
-    // Process encrypted computation (placeholder for Arcium integration)
-    pub fn process_encrypted_computation(ctx: Context<ProcessEncryptedComputation>) -> Result<()> {
-        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
-        
-        // Only the authority can process computation
-        require!(
-            *ctx.accounts.user.key == encrypted_compute.authority,
-            ErrorCode::Unauthorized
-        );
+    // Convert an account written in the devnet (v0) layout to the current one. The account
+    // is reallocated and rewritten in place, so its address and every account derived from
+    // it stay valid. v0 kept a string data_hash, so the real digest must be supplied.
+    pub fn migrate_encrypted_compute(ctx: Context<MigrateEncryptedCompute>, data_hash: ContentDigest) -> Result<()> {
+        let account_info = ctx.accounts.encrypted_compute.to_account_info();
 
-        // Ensure the account has been initialized with data
-        require!(
-            !encrypted_compute.encrypted_data.is_empty(),
-            ErrorCode::NoEncryptedData
-        );
+        let v0 = {
+            let data = account_info.try_borrow_data()?;
+            EncryptedComputeV0::decode(&data, account_info.key, ctx.program_id)?
+        };
+
+        // Only the authority can migrate the account
+        require!(v0.authority == ctx.accounts.user.key(), ErrorCode::Unauthorized);
+
+        let migrated = v0.upgrade(data_hash)?;
+
+        // Top up or hand back rent for the new size
+        let rent = Rent::get()?.minimum_balance(EncryptedCompute::LEN);
+        let lamports = account_info.lamports();
+        if lamports < rent {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.user.to_account_info(),
+                        to: account_info.clone(),
+                    },
+                ),
+                rent - lamports,
+            )?;
+        }
+        account_info.resize(EncryptedCompute::LEN)?;
+        if lamports > rent {
+            account_info.sub_lamports(lamports - rent)?;
+            ctx.accounts.user.add_lamports(lamports - rent)?;
+        }
+
+        let mut data = account_info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data;
+        migrated.try_serialize(&mut writer)?;
 
-        // Placeholder for actual encrypted computation
-        encrypted_compute.status = EncryptedComputeStatus::Processed;
-        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
-        
         Ok(())
     }
 
-    // Apply a time-lock to encrypted data
-    pub fn apply_time_lock(ctx: Context<ApplyTimeLock>, expiration_timestamp: i64) -> Result<()> {
+    // Start staging a ciphertext too large to store inline. Chunks of any earlier,
+    // unfinished upload are abandoned and can be closed.
+    pub fn begin_ciphertext_upload(ctx: Context<BeginCiphertextUpload>) -> Result<()> {
         let encrypted_compute = &mut ctx.accounts.encrypted_compute;
 
-        // Only the authority can apply a time-lock
-        require!(
-            *ctx.accounts.user.key == encrypted_compute.authority,
-            ErrorCode::Unauthorized
-        );
+        // Only the authority or a delegate with update permission can upload data
+        encrypted_compute.require_permission(ctx.accounts.user.key, PERMISSION_UPDATE)?;
 
-        // Verify that the expiration timestamp is in the future
-        let current_time = Clock::get()?.unix_timestamp;
-        require!(
-            expiration_timestamp > current_time,
-            ErrorCode::InvalidTimeLock
-        );
+        // Multisig-governed accounts only update through a proposal
+        encrypted_compute.require_single_authority()?;
 
-        // Apply the time-lock
-        encrypted_compute.time_lock_expiration = expiration_timestamp;
-        encrypted_compute.is_locked = true;
-        encrypted_compute.status = EncryptedComputeStatus::TimeLocked;
-        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+        // Respect the account's time-lock policy
+        encrypted_compute.require_not_time_locked(LOCK_GUARD_UPDATE)?;
+        encrypted_compute.require_active()?;
+
+        encrypted_compute.begin_ciphertext_upload();
 
         Ok(())
     }
 
-    // Revoke a time-lock before expiration (if still active)
-    pub fn revoke_time_lock(ctx: Context<RevokeTimeLock>) -> Result<()> {
+    // Store the next chunk of the ciphertext being uploaded
+    pub fn append_ciphertext_chunk(ctx: Context<AppendCiphertextChunk>, index: u32, data: Vec<u8>) -> Result<()> {
+        let encrypted_compute_key = ctx.accounts.encrypted_compute.key();
         let encrypted_compute = &mut ctx.accounts.encrypted_compute;
 
-        // Only the authority can revoke a time-lock
-        require!(
-            *ctx.accounts.user.key == encrypted_compute.authority,
-            ErrorCode::Unauthorized
-        );
+        // Only the authority or a delegate with update permission can upload data, and
+        // multisig-governed accounts only through a proposal
+        authorize_action(
+            encrypted_compute,
+            ctx.accounts.user.key,
+            PERMISSION_UPDATE,
+            ctx.accounts.proposal.as_deref(),
+            ctx.accounts.proposer.as_ref(),
+            || MultisigAction::AppendCiphertextChunk { index, data: data.clone() },
+        )?;
 
-        // Check that the time-lock is active but not yet expired
-        let current_time = Clock::get()?.unix_timestamp;
-        require!(
-            encrypted_compute.is_locked && 
-            !encrypted_compute.unlock_conditions_met && 
-            encrypted_compute.time_lock_expiration > current_time,
-            ErrorCode::TimeLockNotActive
-        );
+        require!(!data.is_empty(), ErrorCode::InvalidData);
+        require!(data.len() <= MAX_CIPHERTEXT_CHUNK_LEN, ErrorCode::DataTooLong);
+        encrypted_compute.record_ciphertext_chunk(index)?;
 
-        // Revoke the time-lock
-        encrypted_compute.time_lock_expiration = 0;
-        encrypted_compute.status = EncryptedComputeStatus::Unlocked;
-        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+        let chunk = &mut ctx.accounts.chunk;
+        chunk.encrypted_compute = encrypted_compute_key;
+        chunk.generation = encrypted_compute.ciphertext_generation;
+        chunk.index = index;
+        chunk.data = data;
+        chunk.bump = ctx.bumps.chunk;
 
         Ok(())
     }
 
-    // Check if time-lock has expired and update state accordingly
-    pub fn check_time_lock_status(ctx: Context<CheckTimeLockStatus>) -> Result<()> {
+    // Check the staged chunks, passed in order as remaining accounts, against the
+    // sha256 of the whole ciphertext and make them the account's data
+    pub fn finalize_ciphertext<'info>(ctx: Context<'_, '_, 'info, 'info, FinalizeCiphertext<'info>>,
+                                      expected_hash: [u8; 32], metadata: CiphertextMetadata) -> Result<()> {
+        let encrypted_compute_key = ctx.accounts.encrypted_compute.key();
         let encrypted_compute = &mut ctx.accounts.encrypted_compute;
 
-        // Check if time-lock has expired
-        let current_time = Clock::get()?.unix_timestamp;
-        
-        if encrypted_compute.is_locked && 
-           !encrypted_compute.unlock_conditions_met && 
-           current_time >= encrypted_compute.time_lock_expiration {
-            // Time-lock has expired
-            encrypted_compute.is_locked = false;
-            encrypted_compute.unlock_conditions_met = true;
-            encrypted_compute.status = EncryptedComputeStatus::Unlocked;
-            encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+        // Only the authority or a delegate with update permission can upload data, and
+        // multisig-governed accounts only through a proposal
+        authorize_action(
+            encrypted_compute,
+            ctx.accounts.user.key,
+            PERMISSION_UPDATE,
+            ctx.accounts.proposal.as_deref(),
+            ctx.accounts.proposer.as_ref(),
+            || MultisigAction::FinalizeCiphertext { expected_hash, metadata: metadata.clone() },
+        )?;
+
+        let generation = encrypted_compute.ciphertext_generation;
+        let mut chunks = Vec::with_capacity(ctx.remaining_accounts.len());
+        for (index, account_info) in ctx.remaining_accounts.iter().enumerate() {
+            let chunk = Account::<CiphertextChunk>::try_from(account_info)?;
+            let (expected_key, _) = Pubkey::find_program_address(
+                &[b"ciphertext_chunk", encrypted_compute_key.as_ref(), &generation.to_le_bytes(), &(index as u32).to_le_bytes()],
+                ctx.program_id,
+            );
+            require!(account_info.key() == expected_key, ErrorCode::CiphertextHashMismatch);
+            chunks.push(chunk.into_inner().data);
         }
 
+        let current_time = Clock::get()?.unix_timestamp;
+        let chunk_data: Vec<&[u8]> = chunks.iter().map(Vec::as_slice).collect();
+        encrypted_compute.finalize_ciphertext(&chunk_data, expected_hash, metadata, current_time)?;
+        encrypted_compute.updated_at = current_time;
+
         Ok(())
     }
 
-    // Close the encrypted compute account and reclaim rent
-    pub fn close_encrypted_compute(ctx: Context<CloseEncryptedCompute>) -> Result<()> {
+    // Point the account at a ciphertext held off-chain (IPFS, Arweave or any URI). The
+    // digest is stored alongside the pointer so fetched content can be checked against it.
+    pub fn set_external_ciphertext(ctx: Context<SetExternalCiphertext>, pointer: ExternalCiphertext) -> Result<()> {
         let encrypted_compute = &mut ctx.accounts.encrypted_compute;
 
-        // Only the authority can close the account
+        // Only the authority or a delegate with update permission can update data
+        encrypted_compute.require_permission(ctx.accounts.user.key, PERMISSION_UPDATE)?;
+
+        // Multisig-governed accounts only update through a proposal
+        encrypted_compute.require_single_authority()?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        encrypted_compute.set_external_ciphertext(pointer, current_time)?;
+        encrypted_compute.updated_at = current_time;
+
+        Ok(())
+    }
+
+    // Reclaim the rent of a chunk that is no longer part of the account's data
+    pub fn close_ciphertext_chunk(ctx: Context<CloseCiphertextChunk>) -> Result<()> {
+        let encrypted_compute = &ctx.accounts.encrypted_compute;
+
+        // Only the authority or a delegate with update permission can manage uploads
+        encrypted_compute.require_permission(ctx.accounts.user.key, PERMISSION_UPDATE)?;
+
         require!(
-            *ctx.accounts.user.key == encrypted_compute.authority,
-            ErrorCode::Unauthorized
+            !encrypted_compute.chunk_in_use(ctx.accounts.chunk.generation),
+            ErrorCode::CiphertextChunkInUse
         );
 
-        // Update status to closed
-        encrypted_compute.status = EncryptedComputeStatus::Closed;
-        
         Ok(())
     }
 
-    // Verify a zero-knowledge proof
-    pub fn verify_zk_proof(ctx: Context<VerifyZKProof>, 
-                           proof_data: [u8; 256], 
-                           public_inputs: [u8; 128]) -> Result<()> {
+    // Queue an encrypted computation over this account's ciphertext and any further input
+    // accounts passed as remaining accounts. The fee is held in a per-request escrow, in
+    // SOL or, when a fee mint is passed, in a whitelisted SPL token, until an executor
+    // submits a result or the request times out.
+    pub fn queue_computation<'info>(ctx: Context<'_, '_, 'info, 'info, QueueComputation<'info>>,
+                                    fee: u64,
+                                    timeout: i64,
+                                    callback: Option<ComputationCallback>) -> Result<()> {
+        let encrypted_compute_key = ctx.accounts.encrypted_compute.key();
         let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+        let definition = &ctx.accounts.definition;
+        let requester = ctx.accounts.user.key;
+
+        // Only the authority or a delegate with process permission can queue computation; a
+        // multisig-governed account's ciphertext isn't released on a single signature
+        authorize_action(
+            encrypted_compute,
+            requester,
+            PERMISSION_PROCESS,
+            ctx.accounts.proposal.as_deref(),
+            ctx.accounts.proposer.as_ref(),
+            || MultisigAction::QueueComputation {
+                definition: definition.key(),
+                inputs: ctx.remaining_accounts.iter().map(|input| input.key()).collect(),
+                fee_mint: ctx.accounts.fee_mint.as_ref().map(|mint| mint.key()),
+                fee,
+                timeout,
+                callback: callback.clone(),
+            },
+        )?;
+
+        // Respect the account's time-lock policy
+        encrypted_compute.require_not_time_locked(LOCK_GUARD_PROCESS)?;
+        encrypted_compute.require_active()?;
 
-        // Only the authority can verify proofs
+        // Ensure the account holds a ciphertext the definition's circuit can read
+        definition.spec.require_input(encrypted_compute)?;
+        // New requests always run the latest live version of a definition
+        require!(!definition.deprecated, ErrorCode::ComputationDefinitionDeprecated);
+        require!(!definition.superseded, ErrorCode::ComputationDefinitionSuperseded);
         require!(
-            *ctx.accounts.user.key == encrypted_compute.authority,
-            ErrorCode::Unauthorized
+            timeout > 0 && timeout <= MAX_COMPUTATION_TIMEOUT,
+            ErrorCode::InvalidData
+        );
+        if let Some(callback) = callback.as_ref() {
+            callback.validate()?;
+        }
+        let fee_config = &ctx.accounts.fee_config;
+        let fee_mint = ctx.accounts.fee_mint.as_ref().map(|mint| mint.key());
+        fee_config.require_fee_accounts(fee_mint.as_ref(), ctx.accounts.fee_vault.is_some())?;
+        // This account is always the first input
+        require!(
+            ctx.remaining_accounts.len() < MAX_COMPUTATION_INPUTS,
+            ErrorCode::DataTooLong
         );
 
-        // In a real implementation, this would call a ZK proof verification algorithm
-        // For this example, we'll perform a basic validation
-        // This is a simplified placeholder - real verification would be more complex
-        
-        // Example validation: check that proof data is not all zeros
-        let all_zeros = proof_data.iter().all(|&x| x == 0);
+        // The requester must be allowed to process every input it references
+        let mut inputs = vec![encrypted_compute_key];
+        let current_time = Clock::get()?.unix_timestamp;
+        for account_info in ctx.remaining_accounts.iter() {
+            let input = Account::<EncryptedCompute>::try_from(account_info)?;
+            require!(!inputs.contains(&input.key()), ErrorCode::InvalidData);
+            require!(
+                input.has_permission(requester, PERMISSION_PROCESS, current_time),
+                ErrorCode::Unauthorized
+            );
+            input.require_not_time_locked_at(LOCK_GUARD_PROCESS, current_time)?;
+            input.require_active()?;
+            definition.spec.require_input(&input)?;
+            inputs.push(input.key());
+        }
         require!(
-            !all_zeros,
-            ErrorCode::InvalidZKProof
+            inputs.len() == definition.spec.input_arity as usize,
+            ErrorCode::ComputationArityMismatch
         );
 
-        // Update status to reflect that a proof was verified
-        encrypted_compute.status = EncryptedComputeStatus::ProofVerified;
-        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+        let request = &mut ctx.accounts.computation_request;
+        request.encrypted_compute = encrypted_compute_key;
+        request.request_id = encrypted_compute.computations_queued;
+        request.requester = *requester;
+        request.definition_id = definition.definition_id.clone();
+        request.definition_version = definition.version;
+        request.inputs = inputs;
+        request.fee = fee;
+        request.status = ComputationStatus::Queued;
+        request.assigned_executor = None;
+        request.executor = None;
+        request.encrypted_outputs = Vec::new();
+        request.compute_cost = 0;
+        request.result_proof = None;
+        request.callback = callback;
+        request.fee_mint = fee_mint;
+        request.queued_at = current_time;
+        request.timeout_at = current_time.saturating_add(timeout);
+        request.completed_at = 0;
+        request.slashed = false;
+        request.bump = ctx.bumps.computation_request;
+
+        // Fee terms are fixed when the request is queued
+        let fee_escrow = &mut ctx.accounts.fee_escrow;
+        fee_escrow.request = ctx.accounts.computation_request.key();
+        fee_escrow.mint = fee_mint;
+        fee_escrow.amount = fee;
+        fee_escrow.protocol_fee_bps = fee_config.protocol_fee_bps;
+        fee_escrow.treasury = fee_config.treasury;
+        fee_escrow.bump = ctx.bumps.fee_escrow;
+
+        if fee > 0 {
+            match fee_mint {
+                // SOL fees are held by the escrow account on top of its rent
+                None => system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: ctx.accounts.user.to_account_info(),
+                            to: ctx.accounts.fee_escrow.to_account_info(),
+                        },
+                    ),
+                    fee,
+                )?,
+                Some(_) => {
+                    let (Some(fee_vault), Some(requester_token_account), Some(token_program)) = (
+                        ctx.accounts.fee_vault.as_ref(),
+                        ctx.accounts.requester_token_account.as_ref(),
+                        ctx.accounts.token_program.as_ref(),
+                    ) else {
+                        return err!(ErrorCode::InvalidFeeAccounts);
+                    };
+                    token::transfer(
+                        CpiContext::new(
+                            token_program.to_account_info(),
+                            token::Transfer {
+                                from: requester_token_account.to_account_info(),
+                                to: fee_vault.to_account_info(),
+                                authority: ctx.accounts.user.to_account_info(),
+                            },
+                        ),
+                        fee,
+                    )?;
+                }
+            }
+        }
+
+        encrypted_compute.computations_queued = encrypted_compute.computations_queued.saturating_add(1);
 
         Ok(())
     }
 
-    // Store ZK proof data associated with encrypted data
-    pub fn store_zk_proof_data(ctx: Context<StoreZKProofData>,
-                               proof_id: String,
-                               proof_type: String,
-                               proof_data: Vec<u8>,
-                               public_inputs: Vec<u8>) -> Result<()> {
+    // Reserve a queued request for the calling executor. Only it may then submit the
+    // result, and its stake can be slashed if the request times out unanswered.
+    pub fn claim_computation(ctx: Context<ClaimComputation>) -> Result<()> {
+        // Only registered executor nodes with enough stake can take on work
+        ctx.accounts.executor_node.require_eligible(ctx.accounts.executor_registry.min_stake)?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        ctx.accounts.computation_request.claim(&ctx.accounts.definition, ctx.accounts.executor.key(), current_time)
+    }
+
+    // Write the encrypted outputs of a queued computation and pay its fee to the executor.
+    // Definitions with a result circuit take a Groth16 proof of the result; it isn't checked
+    // here, but a proof that fails to verify gets the executor slashed.
+    // If the request has a callback, its accounts followed by the callback program are
+    // passed as remaining accounts and the callback is invoked last, signed by the request.
+    pub fn submit_computation_result<'info>(ctx: Context<'_, '_, 'info, 'info, SubmitComputationResult<'info>>,
+                                            encrypted_outputs: Vec<Vec<u8>>,
+                                            compute_cost: u64,
+                                            result_proof: Option<[u8; groth16::PROOF_LEN]>) -> Result<()> {
         let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+        let executor = ctx.accounts.executor.key;
 
-        // Only the authority can store proof data
-        require!(
-            *ctx.accounts.user.key == encrypted_compute.authority,
-            ErrorCode::Unauthorized
-        );
+        // Only registered executor nodes with enough stake can submit results
+        let executor_node = &mut ctx.accounts.executor_node;
+        executor_node.require_eligible(ctx.accounts.executor_registry.min_stake)?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        let request = &mut ctx.accounts.computation_request;
+        request.complete(&ctx.accounts.definition, *executor, encrypted_outputs, compute_cost, result_proof, current_time)?;
+        executor_node.record_result();
+
+        encrypted_compute.transition_to(EncryptedComputeStatus::Processed)?;
+        encrypted_compute.updated_at = current_time;
+        encrypted_compute.computations_completed = encrypted_compute.computations_completed.saturating_add(1);
+
+        // Pay the executor out of escrow, less the protocol's cut
+        let (executor_share, protocol_share) = ctx.accounts.fee_escrow.release();
+        match ctx.accounts.fee_escrow.mint {
+            None => {
+                ctx.accounts.fee_escrow.sub_lamports(executor_share + protocol_share)?;
+                ctx.accounts.executor.add_lamports(executor_share)?;
+                if protocol_share > 0 {
+                    let treasury = ctx.accounts.treasury.as_ref().ok_or(error!(ErrorCode::InvalidFeeAccounts))?;
+                    treasury.add_lamports(protocol_share)?;
+                }
+            }
+            Some(_) if executor_share + protocol_share > 0 => {
+                let (Some(fee_vault), Some(executor_token_account), Some(token_program)) = (
+                    ctx.accounts.fee_vault.as_ref(),
+                    ctx.accounts.executor_token_account.as_ref(),
+                    ctx.accounts.token_program.as_ref(),
+                ) else {
+                    return err!(ErrorCode::InvalidFeeAccounts);
+                };
+                transfer_from_fee_vault(&ctx.accounts.fee_escrow, fee_vault, executor_token_account, token_program, executor_share)?;
+                if protocol_share > 0 {
+                    let treasury_token_account = ctx.accounts.treasury_token_account.as_ref().ok_or(error!(ErrorCode::InvalidFeeAccounts))?;
+                    transfer_from_fee_vault(&ctx.accounts.fee_escrow, fee_vault, treasury_token_account, token_program, protocol_share)?;
+                }
+            }
+            Some(_) => {}
+        }
+
+        let Some(callback) = request.callback.clone() else {
+            return Ok(());
+        };
+
+        // The callback only ever observes a finished request: persist every account
+        // before handing over control
+        ctx.accounts.computation_request.exit(&crate::ID)?;
+        ctx.accounts.encrypted_compute.exit(&crate::ID)?;
+        ctx.accounts.executor_node.exit(&crate::ID)?;
+        ctx.accounts.fee_escrow.exit(&crate::ID)?;
 
-        // Validate proof data is not empty
+        // Fail up front rather than let the callback run out of compute part-way
         require!(
-            !proof_data.is_empty() && proof_data.len() <= 2048, // Reasonable limit for proof data
-            ErrorCode::InvalidData
+            sol_remaining_compute_units() >= callback.compute_units as u64,
+            ErrorCode::CallbackComputeBudgetTooLow
         );
 
-        // In a complete implementation, we'd store the proof data in the account
-        // For this demo, we'll just verify that it's well-formed
-        // The actual storage would require expanding the account structure
+        let request = &ctx.accounts.computation_request;
+        let instruction = callback.instruction(&ComputationCallbackData {
+            request: request.key(),
+            encrypted_compute: request.encrypted_compute,
+            definition_id: request.definition_id.clone(),
+            encrypted_outputs: request.encrypted_outputs.clone(),
+        })?;
+        callback.check_accounts(ctx.remaining_accounts)?;
 
-        encrypted_compute.status = EncryptedComputeStatus::ProofStored;
-        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+        // The request PDA signs so consumers can tell the call came from this program
+        let encrypted_compute_key = request.encrypted_compute;
+        let request_id = request.request_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"computation_request", encrypted_compute_key.as_ref(), &request_id, &[request.bump]];
+        let mut account_infos = vec![request.to_account_info()];
+        account_infos.extend_from_slice(ctx.remaining_accounts);
+        invoke_signed(&instruction, &account_infos, &[seeds])?;
 
         Ok(())
     }
 
-    // Log an audit event for compliance
-    pub fn log_audit_event(ctx: Context<LogAuditEvent>,
-                          event_type: String,
-                          event_data: String,
-                          user_address: String) -> Result<()> {
-        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+    // Refund a request nobody completed in time. Anyone may crank this; the fee and rent
+    // always go back to the requester.
+    pub fn refund_timed_out_computation(ctx: Context<RefundTimedOutComputation>) -> Result<()> {
+        ctx.accounts.computation_request.require_refundable(Clock::get()?.unix_timestamp)?;
+
+        // SOL fees leave with the escrow's balance when it closes
+        if ctx.accounts.fee_escrow.mint.is_some() {
+            let (Some(fee_vault), Some(requester_token_account), Some(token_program)) = (
+                ctx.accounts.fee_vault.as_ref(),
+                ctx.accounts.requester_token_account.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            ) else {
+                return err!(ErrorCode::InvalidFeeAccounts);
+            };
+            close_fee_vault(&ctx.accounts.fee_escrow, fee_vault, requester_token_account, &ctx.accounts.requester, token_program)?;
+        }
+
+        Ok(())
+    }
 
-        // Only the authority can log audit events
+    // Reclaim the rent of a completed request once its output has been read
+    pub fn close_computation_request(ctx: Context<CloseComputationRequest>) -> Result<()> {
+        let request = &ctx.accounts.computation_request;
+
+        // Only the requester can close its request
         require!(
-            *ctx.accounts.user.key == encrypted_compute.authority,
+            *ctx.accounts.user.key == request.requester,
             ErrorCode::Unauthorized
         );
+        request.require_closable(Clock::get()?.unix_timestamp)?;
 
-        // Validate event data is not empty
-        require!(
-            !event_type.is_empty() && !event_data.is_empty(),
-            ErrorCode::InvalidData
-        );
+        // The fee was paid out on completion; anything sent to the vault since goes back to
+        // the requester along with the rent
+        if ctx.accounts.fee_escrow.mint.is_some() {
+            let (Some(fee_vault), Some(requester_token_account), Some(token_program)) = (
+                ctx.accounts.fee_vault.as_ref(),
+                ctx.accounts.requester_token_account.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            ) else {
+                return err!(ErrorCode::InvalidFeeAccounts);
+            };
+            close_fee_vault(&ctx.accounts.fee_escrow, fee_vault, requester_token_account, &ctx.accounts.user, token_program)?;
+        }
 
-        // In a real implementation, this would store the encrypted audit event
-        // For now, we'll just update the status to reflect that an audit event was logged
-        encrypted_compute.status = EncryptedComputeStatus::AuditLogged;
-        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    // Create the protocol fee configuration; only the program's upgrade authority can, and
+    // it becomes the fee authority
+    pub fn initialize_fee_config(ctx: Context<InitializeFeeConfig>,
+                                 treasury: Pubkey,
+                                 protocol_fee_bps: u16) -> Result<()> {
+        require!(protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS, ErrorCode::InvalidData);
+
+        let fee_config = &mut ctx.accounts.fee_config;
+        fee_config.authority = ctx.accounts.user.key();
+        fee_config.treasury = treasury;
+        fee_config.protocol_fee_bps = protocol_fee_bps;
+        fee_config.allowed_mints = Vec::new();
+        fee_config.bump = ctx.bumps.fee_config;
 
         Ok(())
     }
 
-    // Create an encrypted audit trail
-    pub fn create_audit_trail(ctx: Context<CreateAuditTrail>,
-                             trail_name: String,
-                             access_level: u8) -> Result<()> {
-        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+    // Change the treasury or protocol cut; requests already queued keep their terms
+    pub fn update_fee_config(ctx: Context<ManageFeeConfig>,
+                             treasury: Pubkey,
+                             protocol_fee_bps: u16) -> Result<()> {
+        let fee_config = &mut ctx.accounts.fee_config;
 
-        // Only the authority can create audit trails
+        // Only the fee authority can change fee terms
         require!(
-            *ctx.accounts.user.key == encrypted_compute.authority,
+            *ctx.accounts.user.key == fee_config.authority,
             ErrorCode::Unauthorized
         );
+        require!(protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS, ErrorCode::InvalidData);
 
-        // Validate trail name is not empty
+        fee_config.treasury = treasury;
+        fee_config.protocol_fee_bps = protocol_fee_bps;
+
+        Ok(())
+    }
+
+    // Accept fees in an SPL mint
+    pub fn add_fee_mint(ctx: Context<ManageFeeConfig>, mint: Pubkey) -> Result<()> {
+        let fee_config = &mut ctx.accounts.fee_config;
+
+        // Only the fee authority can whitelist mints
         require!(
-            !trail_name.is_empty(),
-            ErrorCode::InvalidData
+            *ctx.accounts.user.key == fee_config.authority,
+            ErrorCode::Unauthorized
         );
-
-        // Validate access level (0=public, 1=restricted, 2=confidential)
+        require!(!fee_config.allowed_mints.contains(&mint), ErrorCode::InvalidData);
         require!(
-            access_level <= 2,
-            ErrorCode::InvalidAccessLevel
+            fee_config.allowed_mints.len() < MAX_FEE_MINTS,
+            ErrorCode::DataTooLong
         );
 
-        // Update the status to reflect creation of audit trail
-        encrypted_compute.status = EncryptedComputeStatus::AuditTrailCreated;
-        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+        fee_config.allowed_mints.push(mint);
 
         Ok(())
     }
 
-    // Verify a selective disclosure claim
-    pub fn verify_selective_disclosure(ctx: Context<VerifySelectiveDisclosure>,
-                                      claim_data: String,
-                                      proof_data: String) -> Result<()> {
-        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+    // Stop accepting fees in an SPL mint for new requests
+    pub fn remove_fee_mint(ctx: Context<ManageFeeConfig>, mint: Pubkey) -> Result<()> {
+        let fee_config = &mut ctx.accounts.fee_config;
 
-        // Only the authority can verify selective disclosures
+        // Only the fee authority can whitelist mints
         require!(
-            *ctx.accounts.user.key == encrypted_compute.authority,
+            *ctx.accounts.user.key == fee_config.authority,
             ErrorCode::Unauthorized
         );
 
-        // Validate claim and proof data are not empty
-        require!(
-            !claim_data.is_empty() && !proof_data.is_empty(),
-            ErrorCode::InvalidData
-        );
-
-        // In a real implementation, this would verify the ZK proof associated with the claim
-        // For this example, we'll just update the status to reflect verification
-        encrypted_compute.status = EncryptedComputeStatus::SelectiveDisclosureVerified;
-        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+        let index = fee_config
+            .allowed_mints
+            .iter()
+            .position(|allowed| *allowed == mint)
+            .ok_or(error!(ErrorCode::FeeMintNotAllowed))?;
+        fee_config.allowed_mints.remove(index);
 
         Ok(())
     }
 
-    // Issue a verifiable credential
-    pub fn issue_verifiable_credential(ctx: Context<IssueVerifiableCredential>,
-                                      credential_type: String,
-                                      attributes: String,
-                                      recipient: String) -> Result<()> {
-        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+    // Create the registry of executor nodes; only the program's upgrade authority can, and
+    // it becomes the registry authority. Stake is held in SOL, or in `stake_mint` tokens
+    // once the stake vault has been initialized.
+    pub fn initialize_executor_registry(ctx: Context<InitializeExecutorRegistry>,
+                                        stake_mint: Option<Pubkey>,
+                                        min_stake: u64,
+                                        treasury: Pubkey) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.authority = ctx.accounts.user.key();
+        registry.stake_mint = stake_mint;
+        registry.min_stake = min_stake;
+        registry.treasury = treasury;
+        registry.created_at = Clock::get()?.unix_timestamp;
+        registry.bump = ctx.bumps.registry;
 
-        // Only the authority can issue credentials
+        Ok(())
+    }
+
+    // Create the token account that pools SPL stake for every executor node
+    pub fn initialize_executor_stake_vault(ctx: Context<InitializeExecutorStakeVault>) -> Result<()> {
+        // Only the registry authority can set up the vault
         require!(
-            *ctx.accounts.user.key == encrypted_compute.authority,
+            *ctx.accounts.user.key == ctx.accounts.registry.authority,
             ErrorCode::Unauthorized
         );
 
-        // Validate credential type and attributes are not empty
-        require!(
-            !credential_type.is_empty() && !attributes.is_empty(),
-            ErrorCode::InvalidData
-        );
+        Ok(())
+    }
 
-        // Update the status to reflect credential issuance
-        encrypted_compute.status = EncryptedComputeStatus::CredentialIssued;
-        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+    // Register the signer as an executor node; it can submit results once staked
+    pub fn register_executor(ctx: Context<RegisterExecutor>) -> Result<()> {
+        let executor_node = &mut ctx.accounts.executor_node;
+        executor_node.authority = ctx.accounts.user.key();
+        executor_node.stake = 0;
+        executor_node.reputation = EXECUTOR_INITIAL_REPUTATION;
+        executor_node.active = true;
+        executor_node.results_submitted = 0;
+        executor_node.times_slashed = 0;
+        executor_node.registered_at = Clock::get()?.unix_timestamp;
+        executor_node.deregistered_at = 0;
+        executor_node.bump = ctx.bumps.executor_node;
 
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(data_hash: String)]
-pub struct InitializeEncryptedCompute<'info> {
-    #[account(
-        init,
-        payer = user,
-        space = 1355, // discriminator + authority + data_hash_len + data_hash + encrypted_data_len + encrypted_data + status_enum + created_at + updated_at + time_lock_expiration + is_locked + unlock_conditions_met
-        seeds = [b"encrypted_compute", user.key().as_ref(), data_hash.as_bytes()],
-        bump
-    )]
-    pub encrypted_compute: Account<'info, EncryptedCompute>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    // Add stake to an active executor node
+    pub fn stake_executor(ctx: Context<StakeExecutor>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidData);
+        require!(ctx.accounts.executor_node.active, ErrorCode::ExecutorNotEligible);
 
-#[derive(Accounts)]
-pub struct UpdateEncryptedData<'info> {
-    #[account(
-        mut,
-        seeds = [b"encrypted_compute", encrypted_compute.authority.as_ref(), encrypted_compute.data_hash.as_bytes()],
-        bump,
-    )]
-    pub encrypted_compute: Account<'info, EncryptedCompute>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-}
+        match ctx.accounts.registry.stake_mint {
+            // SOL stake is held by the node account itself
+            None => system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.user.to_account_info(),
+                        to: ctx.accounts.executor_node.to_account_info(),
+                    },
+                ),
+                amount,
+            )?,
+            Some(_) => {
+                let (Some(stake_vault), Some(staker_token_account), Some(token_program)) = (
+                    ctx.accounts.stake_vault.as_ref(),
+                    ctx.accounts.staker_token_account.as_ref(),
+                    ctx.accounts.token_program.as_ref(),
+                ) else {
+                    return err!(ErrorCode::InvalidStakeAccounts);
+                };
+                token::transfer(
+                    CpiContext::new(
+                        token_program.to_account_info(),
+                        token::Transfer {
+                            from: staker_token_account.to_account_info(),
+                            to: stake_vault.to_account_info(),
+                            authority: ctx.accounts.user.to_account_info(),
+                        },
+                    ),
+                    amount,
+                )?;
+            }
+        }
 
-#[derive(Accounts)]
-pub struct ProcessEncryptedComputation<'info> {
-    #[account(
-        mut,
-        seeds = [b"encrypted_compute", encrypted_compute.authority.as_ref(), encrypted_compute.data_hash.as_bytes()],
-        bump,
-    )]
-    pub encrypted_compute: Account<'info, EncryptedCompute>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-}
+        let executor_node = &mut ctx.accounts.executor_node;
+        executor_node.stake = executor_node.stake.checked_add(amount).ok_or(error!(ErrorCode::InvalidData))?;
 
-#[derive(Accounts)]
-pub struct ApplyTimeLock<'info> {
-    #[account(
-        mut,
-        seeds = [b"encrypted_compute", encrypted_compute.authority.as_ref(), encrypted_compute.data_hash.as_bytes()],
-        bump,
-    )]
-    pub encrypted_compute: Account<'info, EncryptedCompute>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct RevokeTimeLock<'info> {
-    #[account(
-        mut,
-        seeds = [b"encrypted_compute", encrypted_compute.authority.as_ref(), encrypted_compute.data_hash.as_bytes()],
-        bump,
-    )]
-    pub encrypted_compute: Account<'info, EncryptedCompute>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-}
+    // Stop accepting work; stake stays slashable until the cooldown has passed
+    pub fn deregister_executor(ctx: Context<DeregisterExecutor>) -> Result<()> {
+        let executor_node = &mut ctx.accounts.executor_node;
+        require!(executor_node.active, ErrorCode::ExecutorNotEligible);
 
-#[derive(Accounts)]
-pub struct CheckTimeLockStatus<'info> {
-    #[account(
-        mut,
-        seeds = [b"encrypted_compute", encrypted_compute.authority.as_ref(), encrypted_compute.data_hash.as_bytes()],
-        bump,
-    )]
-    pub encrypted_compute: Account<'info, EncryptedCompute>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-}
+        executor_node.active = false;
+        executor_node.deregistered_at = Clock::get()?.unix_timestamp;
 
-#[derive(Accounts)]
-pub struct CloseEncryptedCompute<'info> {
-    #[account(
-        mut,
-        seeds = [b"encrypted_compute", encrypted_compute.authority.as_ref(), encrypted_compute.data_hash.as_bytes()],
-        bump,
-        close = user
-    )]
-    pub encrypted_compute: Account<'info, EncryptedCompute>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct VerifyZKProof<'info> {
-    #[account(
-        mut,
-        seeds = [b"encrypted_compute", encrypted_compute.authority.as_ref(), encrypted_compute.data_hash.as_bytes()],
+    // Return the remaining stake of a deregistered node and close it
+    pub fn withdraw_executor_stake(ctx: Context<WithdrawExecutorStake>) -> Result<()> {
+        let executor_node = &ctx.accounts.executor_node;
+        executor_node.require_withdrawable(Clock::get()?.unix_timestamp)?;
+
+        // SOL stake leaves with the node account's balance when it closes
+        if ctx.accounts.registry.stake_mint.is_some() && executor_node.stake > 0 {
+            let (Some(stake_vault), Some(staker_token_account), Some(token_program)) = (
+                ctx.accounts.stake_vault.as_ref(),
+                ctx.accounts.staker_token_account.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            ) else {
+                return err!(ErrorCode::InvalidStakeAccounts);
+            };
+            transfer_from_stake_vault(
+                &ctx.accounts.registry,
+                stake_vault,
+                staker_token_account,
+                token_program,
+                executor_node.stake,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // Penalise a node on the evidence of a request: one it claimed and let time out, or a
+    // result whose proof fails to verify. Anyone may crank this; the amount follows from
+    // the reason and the slashed stake goes to the registry treasury.
+    pub fn slash_executor(ctx: Context<SlashExecutor>, reason: SlashReason) -> Result<()> {
+        let request = &mut ctx.accounts.computation_request;
+        request.require_slashable(&ctx.accounts.executor_node.authority, reason, Clock::get()?.unix_timestamp)?;
+
+        // A result is only wrong if its proof fails against the definition's circuit key
+        if let Some(proof) = request.result_proof.filter(|_| reason == SlashReason::InvalidResult) {
+            let verifying_key = ctx.accounts.verifying_key.as_ref().ok_or(error!(ErrorCode::InvalidVerifyingKey))?;
+            ctx.accounts.definition.spec.require_result_key(verifying_key)?;
+            require!(
+                groth16::verify(&verifying_key.key, &proof, &[request.result_commitment()]).is_err(),
+                ErrorCode::NoSlashingEvidence
+            );
+        }
+        request.slashed = true;
+
+        let slashed = ctx.accounts.executor_node.slash(reason);
+        if slashed == 0 {
+            return Ok(());
+        }
+
+        match ctx.accounts.registry.stake_mint {
+            None => {
+                let treasury = ctx.accounts.treasury.as_ref().ok_or(error!(ErrorCode::InvalidStakeAccounts))?;
+                ctx.accounts.executor_node.sub_lamports(slashed)?;
+                treasury.add_lamports(slashed)?;
+            }
+            Some(_) => {
+                let (Some(stake_vault), Some(treasury_token_account), Some(token_program)) = (
+                    ctx.accounts.stake_vault.as_ref(),
+                    ctx.accounts.treasury_token_account.as_ref(),
+                    ctx.accounts.token_program.as_ref(),
+                ) else {
+                    return err!(ErrorCode::InvalidStakeAccounts);
+                };
+                transfer_from_stake_vault(
+                    &ctx.accounts.registry,
+                    stake_vault,
+                    treasury_token_account,
+                    token_program,
+                    slashed,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Apply a time-lock to encrypted data
+    pub fn apply_time_lock(ctx: Context<ApplyTimeLock>, expiration_timestamp: i64) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority or a delegate with lock permission can apply a time-lock
+        encrypted_compute.require_permission(ctx.accounts.user.key, PERMISSION_LOCK)?;
+
+        // Multisig-governed accounts only lock through a proposal
+        encrypted_compute.require_single_authority()?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        encrypted_compute.apply_time_lock(expiration_timestamp, current_time)?;
+        encrypted_compute.updated_at = current_time;
+
+        Ok(())
+    }
+
+    // Revoke a time-lock before expiration (if still active and not committed)
+    pub fn revoke_time_lock(ctx: Context<RevokeTimeLock>) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority or a delegate with lock permission can revoke a time-lock
+        encrypted_compute.require_permission(ctx.accounts.user.key, PERMISSION_LOCK)?;
+
+        // Multisig-governed accounts only revoke through a proposal
+        encrypted_compute.require_single_authority()?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        encrypted_compute.revoke_time_lock(current_time)?;
+        encrypted_compute.updated_at = current_time;
+
+        Ok(())
+    }
+
+    // Commit to an active time-lock: from now on it can only be extended
+    pub fn commit_time_lock(ctx: Context<CommitTimeLock>) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority or a delegate with lock permission can commit a time-lock
+        encrypted_compute.require_permission(ctx.accounts.user.key, PERMISSION_LOCK)?;
+
+        // Multisig-governed accounts only commit through a proposal
+        encrypted_compute.require_single_authority()?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        encrypted_compute.commit_time_lock(current_time)?;
+        encrypted_compute.updated_at = current_time;
+
+        Ok(())
+    }
+
+    // Push an active time-lock's expiration further out
+    pub fn extend_time_lock(ctx: Context<ExtendTimeLock>, new_expiration: i64) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority or a delegate with lock permission can extend a time-lock
+        encrypted_compute.require_permission(ctx.accounts.user.key, PERMISSION_LOCK)?;
+
+        // Multisig-governed accounts only extend through a proposal
+        encrypted_compute.require_single_authority()?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        encrypted_compute.extend_time_lock(new_expiration, current_time)?;
+        encrypted_compute.updated_at = current_time;
+
+        Ok(())
+    }
+
+    // Bring an active, uncommitted time-lock's expiration closer
+    pub fn shorten_time_lock(ctx: Context<ShortenTimeLock>, new_expiration: i64) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority or a delegate with lock permission can shorten a time-lock
+        encrypted_compute.require_permission(ctx.accounts.user.key, PERMISSION_LOCK)?;
+
+        // Multisig-governed accounts only shorten through a proposal
+        encrypted_compute.require_single_authority()?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        encrypted_compute.shorten_time_lock(new_expiration, current_time)?;
+        encrypted_compute.updated_at = current_time;
+
+        Ok(())
+    }
+
+    // Check if time-lock has expired and update state accordingly
+    pub fn check_time_lock_status(ctx: Context<CheckTimeLockStatus>) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Check if time-lock has expired
+        let current_time = Clock::get()?.unix_timestamp;
+        if encrypted_compute.release_expired_time_lock(current_time) {
+            encrypted_compute.updated_at = current_time;
+        }
+
+        Ok(())
+    }
+
+    // Attach (or clear) a composable unlock policy; it can't change while a lock holds
+    pub fn set_unlock_policy(ctx: Context<SetUnlockPolicy>, unlock_policy: Option<UnlockPolicy>) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority can configure the unlock policy
+        require!(
+            *ctx.accounts.user.key == encrypted_compute.authority,
+            ErrorCode::Unauthorized
+        );
+
+        // Multisig-governed accounts only change it through a proposal
+        encrypted_compute.require_single_authority()?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        encrypted_compute.set_unlock_policy(unlock_policy, current_time)?;
+        encrypted_compute.updated_at = current_time;
+
+        Ok(())
+    }
+
+    // Record that one condition of the unlock policy is satisfied. Releases the lock
+    // as soon as a whole clause of the policy holds.
+    pub fn record_unlock_condition(ctx: Context<RecordUnlockCondition>, condition_index: u8) -> Result<()> {
+        let encrypted_compute_key = ctx.accounts.encrypted_compute.key();
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // A verified proof only counts if it belongs to this account
+        let verified_circuit = match &ctx.accounts.zk_proof_record {
+            Some(record) => {
+                require!(
+                    record.encrypted_compute == encrypted_compute_key,
+                    ErrorCode::InvalidZKProof
+                );
+                require!(record.verified, ErrorCode::InvalidZKProof);
+                Some(record.circuit_id.as_str())
+            }
+            None => None,
+        };
+
+        let clock = Clock::get()?;
+        let evidence = UnlockEvidence {
+            unix_timestamp: clock.unix_timestamp,
+            slot: clock.slot,
+            signer: ctx.accounts.user.key(),
+            verified_circuit,
+        };
+
+        encrypted_compute.record_unlock_condition(condition_index as usize, &evidence)?;
+        encrypted_compute.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Choose which operations a time-lock blocks on this account
+    pub fn set_time_lock_guards(ctx: Context<SetTimeLockGuards>, lock_guards: u16) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority can configure the lock policy
+        require!(
+            *ctx.accounts.user.key == encrypted_compute.authority,
+            ErrorCode::Unauthorized
+        );
+
+        // Multisig-governed accounts only change it through a proposal
+        encrypted_compute.require_single_authority()?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        encrypted_compute.set_time_lock_guards(lock_guards, current_time)?;
+        encrypted_compute.updated_at = current_time;
+
+        Ok(())
+    }
+
+    // Grant (or update) a delegate's permissions on this account
+    pub fn grant_permission(ctx: Context<ManagePermissions>,
+                            delegate: Pubkey,
+                            permissions: u8,
+                            expires_at: Option<i64>) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority can manage delegates
+        require!(
+            *ctx.accounts.user.key == encrypted_compute.authority,
+            ErrorCode::Unauthorized
+        );
+
+        // Multisig-governed accounts only manage delegates through a proposal
+        encrypted_compute.require_single_authority()?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        encrypted_compute.grant_permission(delegate, permissions, expires_at, current_time)?;
+        encrypted_compute.updated_at = current_time;
+
+        Ok(())
+    }
+
+    // Remove a delegate from this account's ACL
+    pub fn revoke_permission(ctx: Context<ManagePermissions>, delegate: Pubkey) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority can manage delegates
+        require!(
+            *ctx.accounts.user.key == encrypted_compute.authority,
+            ErrorCode::Unauthorized
+        );
+
+        // Multisig-governed accounts only manage delegates through a proposal
+        encrypted_compute.require_single_authority()?;
+
+        encrypted_compute.revoke_permission(&delegate)?;
+        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Propose a new authority; the transfer completes when they accept
+    pub fn propose_authority_transfer(ctx: Context<ProposeAuthorityTransfer>, new_authority: Pubkey) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority can hand over the account
+        require!(
+            *ctx.accounts.user.key == encrypted_compute.authority,
+            ErrorCode::Unauthorized
+        );
+
+        // A transfer would sidestep multisig governance
+        encrypted_compute.require_single_authority()?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        encrypted_compute.propose_authority_transfer(new_authority, current_time)?;
+        encrypted_compute.updated_at = current_time;
+
+        Ok(())
+    }
+
+    // Accept a pending transfer, signed by the proposed authority
+    pub fn accept_authority_transfer(ctx: Context<AcceptAuthorityTransfer>) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        encrypted_compute.accept_authority_transfer(ctx.accounts.new_authority.key, current_time)?;
+        encrypted_compute.updated_at = current_time;
+
+        Ok(())
+    }
+
+    // Withdraw a pending transfer
+    pub fn cancel_authority_transfer(ctx: Context<CancelAuthorityTransfer>) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority can cancel a transfer
+        require!(
+            *ctx.accounts.user.key == encrypted_compute.authority,
+            ErrorCode::Unauthorized
+        );
+
+        require!(
+            encrypted_compute.pending_authority.is_some(),
+            ErrorCode::NoPendingAuthorityTransfer
+        );
+        encrypted_compute.pending_authority = None;
+        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Close the encrypted compute account and reclaim rent
+    pub fn close_encrypted_compute(ctx: Context<CloseEncryptedCompute>) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority can close the account
+        require!(
+            *ctx.accounts.user.key == encrypted_compute.authority,
+            ErrorCode::Unauthorized
+        );
+
+        // Multisig-governed accounts only close through a proposal
+        encrypted_compute.require_single_authority()?;
+
+        // Update status to closed, respecting the account's time-lock policy
+        encrypted_compute.mark_closed(Clock::get()?.unix_timestamp)?;
+        
+        Ok(())
+    }
+
+    // Hand governance of the account to an M-of-N multisig; once enabled it can only
+    // be changed or removed through a multisig proposal
+    pub fn enable_multisig(ctx: Context<EnableMultisig>, multisig: AuthorityMultisig) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority can enable multisig governance
+        require!(
+            *ctx.accounts.user.key == encrypted_compute.authority,
+            ErrorCode::Unauthorized
+        );
+        encrypted_compute.require_single_authority()?;
+        require!(
+            encrypted_compute.pending_authority.is_none(),
+            ErrorCode::InvalidData
+        );
+
+        encrypted_compute.set_multisig(Some(multisig))?;
+        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Propose a privileged action; the proposer's approval is recorded immediately
+    pub fn propose_multisig_action(ctx: Context<ProposeMultisigAction>, action: MultisigAction) -> Result<()> {
+        let encrypted_compute_key = ctx.accounts.encrypted_compute.key();
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only multisig signers can propose
+        let signer_index = encrypted_compute.multisig_signer_index(ctx.accounts.user.key)?;
+        action.validate()?;
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.encrypted_compute = encrypted_compute_key;
+        proposal.proposal_id = encrypted_compute.multisig_proposals_created;
+        proposal.proposer = ctx.accounts.user.key();
+        proposal.action = action;
+        proposal.multisig_nonce = encrypted_compute.multisig_nonce;
+        proposal.approvals = 0;
+        proposal.created_at = Clock::get()?.unix_timestamp;
+        proposal.bump = ctx.bumps.proposal;
+        proposal.approve(signer_index);
+
+        encrypted_compute.multisig_proposals_created = encrypted_compute.multisig_proposals_created.saturating_add(1);
+
+        Ok(())
+    }
+
+    // Add a multisig signer's approval to a pending proposal
+    pub fn approve_multisig_action(ctx: Context<ApproveMultisigAction>) -> Result<()> {
+        let encrypted_compute = &ctx.accounts.encrypted_compute;
+        let proposal = &mut ctx.accounts.proposal;
+
+        // Only multisig signers can approve, and only under the configuration they proposed against
+        let signer_index = encrypted_compute.multisig_signer_index(ctx.accounts.user.key)?;
+        require!(
+            proposal.multisig_nonce == encrypted_compute.multisig_nonce,
+            ErrorCode::MultisigProposalStale
+        );
+
+        proposal.approve(signer_index);
+
+        Ok(())
+    }
+
+    // Run a proposal once it has reached the threshold; the proposal account is closed
+    pub fn execute_multisig_action(ctx: Context<ExecuteMultisigAction>) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+        let proposal = &ctx.accounts.proposal;
+
+        // Only multisig signers can execute
+        encrypted_compute.multisig_signer_index(ctx.accounts.user.key)?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        encrypted_compute.execute_multisig_action(proposal, current_time)?;
+        encrypted_compute.updated_at = current_time;
+
+        // A closed record returns its rent to the authority
+        if encrypted_compute.status == EncryptedComputeStatus::Closed {
+            encrypted_compute.close(ctx.accounts.authority.to_account_info())?;
+        }
+
+        Ok(())
+    }
+
+    // Withdraw a proposal; its proposer can do so at any time
+    pub fn cancel_multisig_action(ctx: Context<CancelMultisigAction>) -> Result<()> {
+        require!(
+            *ctx.accounts.user.key == ctx.accounts.proposal.proposer,
+            ErrorCode::Unauthorized
+        );
+
+        Ok(())
+    }
+
+    // Create the registry that governs circuit verifying keys; only the program's upgrade
+    // authority can, and it becomes the registry authority
+    pub fn initialize_verifier_registry(ctx: Context<InitializeVerifierRegistry>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.authority = ctx.accounts.user.key();
+        registry.created_at = Clock::get()?.unix_timestamp;
+        registry.bump = ctx.bumps.registry;
+
+        Ok(())
+    }
+
+    // Register the first version of a circuit's verifying key
+    pub fn register_verifying_key(ctx: Context<RegisterVerifyingKey>,
+                                  circuit_id: String,
+                                  key: Groth16VerifyingKey) -> Result<()> {
+        // Only the registry authority can register keys
+        ctx.accounts.registry.require_authority(ctx.accounts.user.key)?;
+
+        let verifying_key = VerifyingKey::register(
+            circuit_id,
+            key,
+            Clock::get()?.unix_timestamp,
+            ctx.bumps.verifying_key,
+        )?;
+        ctx.accounts.verifying_key.set_inner(verifying_key);
+
+        Ok(())
+    }
+
+    // Publish the next version of a circuit's key; older versions stay usable for old proofs
+    pub fn rotate_verifying_key(ctx: Context<RotateVerifyingKey>,
+                                key: Groth16VerifyingKey) -> Result<()> {
+        // Only the registry authority can rotate keys
+        ctx.accounts.registry.require_authority(ctx.accounts.user.key)?;
+
+        let next_key = ctx.accounts.current_key.rotate(
+            key,
+            Clock::get()?.unix_timestamp,
+            ctx.bumps.next_key,
+        )?;
+        ctx.accounts.next_key.set_inner(next_key);
+
+        Ok(())
+    }
+
+    // Stop accepting proofs against a key version (e.g. after a compromised setup)
+    pub fn deprecate_verifying_key(ctx: Context<DeprecateVerifyingKey>) -> Result<()> {
+        // Only the registry authority can deprecate keys
+        ctx.accounts.registry.require_authority(ctx.accounts.user.key)?;
+
+        ctx.accounts.verifying_key.deprecate(Clock::get()?.unix_timestamp)
+    }
+
+    // Create the registry that governs computation definitions; only the program's upgrade
+    // authority can, and it becomes the registry authority
+    pub fn initialize_definition_registry(ctx: Context<InitializeDefinitionRegistry>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.authority = ctx.accounts.user.key();
+        registry.created_at = Clock::get()?.unix_timestamp;
+        registry.bump = ctx.bumps.registry;
+
+        Ok(())
+    }
+
+    // Register the first version of a computation definition
+    pub fn register_computation_definition(ctx: Context<RegisterComputationDefinition>,
+                                           definition_id: String,
+                                           spec: ComputationSpec) -> Result<()> {
+        // Only the registry authority can register definitions
+        require!(
+            *ctx.accounts.user.key == ctx.accounts.registry.authority,
+            ErrorCode::Unauthorized
+        );
+
+        require!(!definition_id.is_empty(), ErrorCode::InvalidData);
+        require!(definition_id.len() <= MAX_DEFINITION_ID_LEN, ErrorCode::DataTooLong);
+        spec.validate()?;
+
+        let definition = &mut ctx.accounts.definition;
+        definition.definition_id = definition_id;
+        definition.version = 1;
+        definition.spec = spec;
+        definition.superseded = false;
+        definition.deprecated = false;
+        definition.registered_at = Clock::get()?.unix_timestamp;
+        definition.deprecated_at = 0;
+        definition.bump = ctx.bumps.definition;
+
+        Ok(())
+    }
+
+    // Publish the next version of a definition; requests already queued keep their version
+    pub fn rotate_computation_definition(ctx: Context<RotateComputationDefinition>,
+                                         spec: ComputationSpec) -> Result<()> {
+        // Only the registry authority can rotate definitions
+        require!(
+            *ctx.accounts.user.key == ctx.accounts.registry.authority,
+            ErrorCode::Unauthorized
+        );
+
+        // Rotation always continues from the latest version
+        let current_definition = &mut ctx.accounts.current_definition;
+        require!(!current_definition.superseded, ErrorCode::ComputationDefinitionSuperseded);
+        spec.validate()?;
+
+        current_definition.superseded = true;
+
+        let next_definition = &mut ctx.accounts.next_definition;
+        next_definition.definition_id = current_definition.definition_id.clone();
+        next_definition.version = current_definition.version + 1;
+        next_definition.spec = spec;
+        next_definition.superseded = false;
+        next_definition.deprecated = false;
+        next_definition.registered_at = Clock::get()?.unix_timestamp;
+        next_definition.deprecated_at = 0;
+        next_definition.bump = ctx.bumps.next_definition;
+
+        Ok(())
+    }
+
+    // Stop accepting new requests for a definition version
+    pub fn deprecate_computation_definition(ctx: Context<DeprecateComputationDefinition>) -> Result<()> {
+        // Only the registry authority can deprecate definitions
+        require!(
+            *ctx.accounts.user.key == ctx.accounts.registry.authority,
+            ErrorCode::Unauthorized
+        );
+
+        let definition = &mut ctx.accounts.definition;
+        require!(!definition.deprecated, ErrorCode::ComputationDefinitionDeprecated);
+
+        definition.deprecated = true;
+        definition.deprecated_at = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Verify a Groth16 zero-knowledge proof over BN254 against a registered circuit key
+    pub fn verify_zk_proof(ctx: Context<VerifyZKProof>,
+                           circuit_id: String,
+                           key_version: u32,
+                           proof_data: [u8; 256],
+                           public_inputs: Vec<[u8; 32]>) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+        let verifying_key = &ctx.accounts.verifying_key;
+
+        // Only the authority or a delegate with prove permission can verify proofs
+        encrypted_compute.require_permission(ctx.accounts.user.key, PERMISSION_PROVE)?;
+
+        // Respect the account's time-lock policy
+        encrypted_compute.require_not_time_locked(LOCK_GUARD_PROVE)?;
+
+        // The named circuit version must still be accepted by the registry
+        require!(
+            verifying_key.circuit_id == circuit_id && verifying_key.version == key_version,
+            ErrorCode::InvalidVerifyingKey
+        );
+        require!(!verifying_key.deprecated, ErrorCode::VerifyingKeyDeprecated);
+
+        // Run the pairing check through the alt_bn128 syscalls
+        groth16::verify(&verifying_key.key, &proof_data, &public_inputs)?;
+
+        // Record that a proof was verified
+        encrypted_compute.require_active()?;
+        encrypted_compute.proofs_verified = encrypted_compute.proofs_verified.saturating_add(1);
+        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Store ZK proof data associated with encrypted data
+    pub fn store_zk_proof_data(ctx: Context<StoreZKProofData>,
+                               proof_id: String,
+                               circuit_id: String,
+                               key_version: u32,
+                               proof_type: String,
+                               proof_data: Vec<u8>,
+                               public_inputs: Vec<u8>) -> Result<()> {
+        let encrypted_compute_key = ctx.accounts.encrypted_compute.key();
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+        let verifying_key = &ctx.accounts.verifying_key;
+
+        // Only the authority or a delegate with prove permission can store proof data
+        encrypted_compute.require_permission(ctx.accounts.user.key, PERMISSION_PROVE)?;
+
+        // Respect the account's time-lock policy
+        encrypted_compute.require_not_time_locked(LOCK_GUARD_PROVE)?;
+
+        require!(!proof_id.is_empty(), ErrorCode::InvalidData);
+        require!(
+            proof_id.len() <= MAX_PROOF_ID_LEN && proof_type.len() <= MAX_PROOF_TYPE_LEN,
+            ErrorCode::DataTooLong
+        );
+
+        // Persist the exact proof so auditors can re-check it later
+        let clock = Clock::get()?;
+        let record = &mut ctx.accounts.zk_proof_record;
+        record.encrypted_compute = encrypted_compute_key;
+        record.proof_id = proof_id;
+        record.circuit_id = circuit_id;
+        record.key_version = key_version;
+        record.proof_type = proof_type;
+        record.proof_data = proof_data;
+        record.public_inputs = public_inputs;
+        record.submitter = ctx.accounts.user.key();
+        record.slot = clock.slot;
+        record.verified = false;
+        record.verified_slot = 0;
+        record.bump = ctx.bumps.zk_proof_record;
+
+        // The proof must target a live key and match its shape
+        record.require_matches(verifying_key)?;
+
+        encrypted_compute.require_active()?;
+        encrypted_compute.proofs_stored = encrypted_compute.proofs_stored.saturating_add(1);
+        encrypted_compute.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Verify a previously stored proof and record the outcome on its record
+    pub fn verify_stored_zk_proof(ctx: Context<VerifyStoredZKProof>) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+        let verifying_key = &ctx.accounts.verifying_key;
+        let record = &mut ctx.accounts.zk_proof_record;
+
+        // Only the authority or a delegate with prove permission can verify proofs
+        encrypted_compute.require_permission(ctx.accounts.user.key, PERMISSION_PROVE)?;
+
+        // Respect the account's time-lock policy
+        encrypted_compute.require_not_time_locked(LOCK_GUARD_PROVE)?;
+
+        // The record must be checked against the exact key version it was stored for
+        record.require_matches(verifying_key)?;
+
+        let proof: [u8; groth16::PROOF_LEN] = record.proof_data.as_slice()
+            .try_into()
+            .map_err(|_| error!(ErrorCode::InvalidZKProof))?;
+        let public_inputs: Vec<[u8; 32]> = record.public_inputs
+            .chunks_exact(groth16::FIELD_ELEMENT_LEN)
+            .map(|chunk| chunk.try_into().unwrap())
+            .collect();
+
+        groth16::verify(&verifying_key.key, &proof, &public_inputs)?;
+
+        let clock = Clock::get()?;
+        record.verified = true;
+        record.verified_slot = clock.slot;
+
+        encrypted_compute.require_active()?;
+        encrypted_compute.proofs_verified = encrypted_compute.proofs_verified.saturating_add(1);
+        encrypted_compute.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Close a stored proof record and refund its rent to whoever stored it
+    pub fn close_zk_proof_record(ctx: Context<CloseZKProofRecord>) -> Result<()> {
+        // Only the authority can close proof records
+        require!(
+            *ctx.accounts.user.key == ctx.accounts.encrypted_compute.authority,
+            ErrorCode::Unauthorized
+        );
+
+        Ok(())
+    }
+
+    // Open the next audit log page once the current one is full
+    pub fn open_audit_log_page(ctx: Context<OpenAuditLogPage>) -> Result<()> {
+        // Only parties allowed to append to the trail can extend its log
+        require!(
+            ctx.accounts.audit_trail.can_append(ctx.accounts.user.key, &ctx.accounts.encrypted_compute.authority),
+            ErrorCode::Unauthorized
+        );
+
+        let audit_log = &mut ctx.accounts.audit_log;
+        require!(
+            audit_log.entry_count == audit_log.page_count as u64 * AUDIT_ENTRIES_PER_PAGE as u64,
+            ErrorCode::AuditLogPageNotFull
+        );
+
+        let page = &mut ctx.accounts.audit_log_page;
+        page.audit_log = audit_log.key();
+        page.page_index = audit_log.page_count;
+        page.entries = Vec::new();
+        page.bump = ctx.bumps.audit_log_page;
+
+        audit_log.page_count += 1;
+
+        Ok(())
+    }
+
+    // Log an audit event for compliance into the chosen audit trail
+    pub fn log_audit_event(ctx: Context<LogAuditEvent>,
+                          event_type: String,
+                          event_data: String,
+                          user_address: String) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the trail owner, its auditors and audit delegates can log audit events
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            ctx.accounts.audit_trail.can_append(ctx.accounts.user.key, &encrypted_compute.authority) ||
+            encrypted_compute.has_permission(ctx.accounts.user.key, PERMISSION_AUDIT, now),
+            ErrorCode::Unauthorized
+        );
+
+        // Respect the account's time-lock policy
+        encrypted_compute.require_not_time_locked(LOCK_GUARD_AUDIT)?;
+
+        // Validate event data is not empty
+        require!(
+            !event_type.is_empty() && !event_data.is_empty(),
+            ErrorCode::InvalidData
+        );
+        require!(
+            event_type.len() <= MAX_AUDIT_EVENT_TYPE_LEN &&
+            event_data.len() <= MAX_AUDIT_EVENT_DATA_LEN &&
+            user_address.len() <= MAX_AUDIT_USER_ADDRESS_LEN,
+            ErrorCode::DataTooLong
+        );
+
+        let audit_log = &mut ctx.accounts.audit_log;
+        let page = &mut ctx.accounts.audit_log_page;
+        require!(
+            page.entries.len() < AUDIT_ENTRIES_PER_PAGE,
+            ErrorCode::AuditLogPageFull
+        );
+
+        // Chain the new entry onto the current head
+        let clock = Clock::get()?;
+        let mut entry = AuditEntry {
+            index: audit_log.entry_count,
+            timestamp: clock.unix_timestamp,
+            slot: clock.slot,
+            actor: ctx.accounts.user.key(),
+            event_type,
+            event_data,
+            user_address,
+            prev_hash: audit_log.head_hash,
+            entry_hash: [0u8; 32],
+        };
+        entry.entry_hash = entry.compute_hash();
+
+        audit_log.head_hash = entry.entry_hash;
+        audit_log.entry_count += 1;
+        page.entries.push(entry);
+
+        encrypted_compute.require_active()?;
+        encrypted_compute.audit_events_logged = encrypted_compute.audit_events_logged.saturating_add(1);
+        encrypted_compute.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Recompute the hash chain over consecutive pages (passed as remaining accounts)
+    // and check it ends at the recorded head. Anyone may call this.
+    pub fn verify_audit_log_chain<'info>(ctx: Context<'_, '_, 'info, 'info, VerifyAuditLogChain<'info>>,
+                                         start_page: u32) -> Result<()> {
+        let audit_log = &ctx.accounts.audit_log;
+        let audit_log_key = audit_log.key();
+
+        require!(!ctx.remaining_accounts.is_empty(), ErrorCode::InvalidData);
+        require!(
+            start_page as usize + ctx.remaining_accounts.len() == audit_log.page_count as usize,
+            ErrorCode::AuditChainBroken
+        );
+
+        let mut expected_index = start_page as u64 * AUDIT_ENTRIES_PER_PAGE as u64;
+        let mut prev_hash: Option<[u8; 32]> = if start_page == 0 { Some([0u8; 32]) } else { None };
+
+        for (offset, account_info) in ctx.remaining_accounts.iter().enumerate() {
+            let page = Account::<AuditLogPage>::try_from(account_info)?;
+            let (expected_key, _) = Pubkey::find_program_address(
+                &[b"audit_log_page", audit_log_key.as_ref(), &page.page_index.to_le_bytes()],
+                ctx.program_id,
+            );
+            require!(
+                account_info.key() == expected_key &&
+                page.audit_log == audit_log_key &&
+                page.page_index == start_page + offset as u32,
+                ErrorCode::AuditChainBroken
+            );
+
+            for entry in page.entries.iter() {
+                // Gaps, reordering or edits all break one of these checks
+                require!(entry.index == expected_index, ErrorCode::AuditChainBroken);
+                if let Some(prev) = prev_hash {
+                    require!(entry.prev_hash == prev, ErrorCode::AuditChainBroken);
+                }
+                require!(entry.compute_hash() == entry.entry_hash, ErrorCode::AuditChainBroken);
+
+                prev_hash = Some(entry.entry_hash);
+                expected_index += 1;
+            }
+        }
+
+        require!(expected_index == audit_log.entry_count, ErrorCode::AuditChainBroken);
+        require!(
+            prev_hash.unwrap_or([0u8; 32]) == audit_log.head_hash,
+            ErrorCode::AuditChainBroken
+        );
+
+        Ok(())
+    }
+
+    // Create an encrypted audit trail together with its hash-chained log
+    pub fn create_audit_trail(ctx: Context<CreateAuditTrail>,
+                             trail_name: String,
+                             access_level: u8) -> Result<()> {
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority or a delegate with audit permission can create audit trails
+        encrypted_compute.require_permission(ctx.accounts.user.key, PERMISSION_AUDIT)?;
+
+        // Respect the account's time-lock policy
+        encrypted_compute.require_not_time_locked(LOCK_GUARD_AUDIT)?;
+
+        // Validate trail name is not empty
+        require!(
+            !trail_name.is_empty(),
+            ErrorCode::InvalidData
+        );
+        require!(trail_name.len() <= MAX_AUDIT_TRAIL_NAME_LEN, ErrorCode::DataTooLong);
+
+        // Validate access level (0=public, 1=restricted, 2=confidential)
+        let access_level = AuditAccessLevel::from_u8(access_level)?;
+
+        let audit_trail = &mut ctx.accounts.audit_trail;
+        audit_trail.encrypted_compute = encrypted_compute.key();
+        audit_trail.name = trail_name;
+        audit_trail.access_level = access_level;
+        audit_trail.members = Vec::new();
+        audit_trail.created_at = Clock::get()?.unix_timestamp;
+        audit_trail.bump = ctx.bumps.audit_trail;
+
+        let audit_log = &mut ctx.accounts.audit_log;
+        audit_log.audit_trail = audit_trail.key();
+        audit_log.entry_count = 0;
+        audit_log.page_count = 1;
+        audit_log.head_hash = [0u8; 32];
+        audit_log.bump = ctx.bumps.audit_log;
+
+        let first_page = &mut ctx.accounts.audit_log_page;
+        first_page.audit_log = audit_log.key();
+        first_page.page_index = 0;
+        first_page.entries = Vec::new();
+        first_page.bump = ctx.bumps.audit_log_page;
+
+        // Record the creation of the audit trail
+        encrypted_compute.require_active()?;
+        encrypted_compute.audit_trails_created = encrypted_compute.audit_trails_created.saturating_add(1);
+        encrypted_compute.updated_at = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+
+    // Grant a reader or auditor role on an audit trail
+    pub fn add_audit_trail_member(ctx: Context<ManageAuditTrail>,
+                                  member: Pubkey,
+                                  role: AuditTrailRole) -> Result<()> {
+        let audit_trail = &mut ctx.accounts.audit_trail;
+
+        // Only the trail owner, the audited account's current authority, can manage members
+        require!(
+            *ctx.accounts.user.key == ctx.accounts.encrypted_compute.authority,
+            ErrorCode::Unauthorized
+        );
+
+        // Re-adding a member updates their role
+        if let Some(existing) = audit_trail.members.iter_mut().find(|m| m.member == member) {
+            existing.role = role;
+            return Ok(());
+        }
+
+        require!(
+            audit_trail.members.len() < MAX_AUDIT_TRAIL_MEMBERS,
+            ErrorCode::AuditTrailFull
+        );
+        audit_trail.members.push(AuditTrailMember { member, role });
+
+        Ok(())
+    }
+
+    // Remove a reader or auditor from an audit trail
+    pub fn remove_audit_trail_member(ctx: Context<ManageAuditTrail>, member: Pubkey) -> Result<()> {
+        let audit_trail = &mut ctx.accounts.audit_trail;
+
+        // Only the trail owner, the audited account's current authority, can manage members
+        require!(
+            *ctx.accounts.user.key == ctx.accounts.encrypted_compute.authority,
+            ErrorCode::Unauthorized
+        );
+
+        let before = audit_trail.members.len();
+        audit_trail.members.retain(|m| m.member != member);
+        require!(audit_trail.members.len() < before, ErrorCode::InvalidData);
+
+        Ok(())
+    }
+
+    // Succeeds only if the signer may read the trail's encrypted entries. Key custodians
+    // gate decryption keys on this (via CPI or a signed simulation).
+    pub fn authorize_audit_trail_read(ctx: Context<AuthorizeAuditTrailRead>) -> Result<()> {
+        require!(
+            ctx.accounts.audit_trail.can_read(ctx.accounts.reader.key, &ctx.accounts.encrypted_compute.authority),
+            ErrorCode::Unauthorized
+        );
+
+        Ok(())
+    }
+
+    // Verify a selective disclosure: a Groth16 proof, against a registered circuit key, that
+    // attributes committed to by the presented credential satisfy the claim in `claim_inputs`
+    pub fn verify_selective_disclosure(ctx: Context<VerifySelectiveDisclosure>,
+                                      circuit_id: String,
+                                      key_version: u32,
+                                      proof_data: [u8; 256],
+                                      claim_inputs: Vec<[u8; 32]>) -> Result<()> {
+        let encrypted_compute_key = ctx.accounts.encrypted_compute.key();
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+        let verifying_key = &ctx.accounts.verifying_key;
+        let credential = &ctx.accounts.credential;
+
+        // Only the authority or a delegate with prove permission can verify selective disclosures
+        encrypted_compute.require_permission(ctx.accounts.user.key, PERMISSION_PROVE)?;
+
+        // Respect the account's time-lock policy
+        encrypted_compute.require_not_time_locked(LOCK_GUARD_DISCLOSURE)?;
+
+        // The claim must come from a live credential this account issued to the signer
+        let current_time = Clock::get()?.unix_timestamp;
+        credential.require_presented(
+            &encrypted_compute_key,
+            ctx.accounts.user.key,
+            &ctx.accounts.revocation_list,
+            current_time,
+        )?;
+
+        // The named circuit version must still be accepted by the registry
+        require!(
+            verifying_key.circuit_id == circuit_id && verifying_key.version == key_version,
+            ErrorCode::InvalidVerifyingKey
+        );
+        require!(!verifying_key.deprecated, ErrorCode::VerifyingKeyDeprecated);
+
+        // The proof is bound to the credential through its attribute commitment, which leads
+        // the public inputs
+        let mut public_inputs = Vec::with_capacity(claim_inputs.len() + 1);
+        public_inputs.push(credential.disclosure_commitment());
+        public_inputs.extend(claim_inputs);
+        groth16::verify(&verifying_key.key, &proof_data, &public_inputs)?;
+
+        // Record that a disclosure was verified
+        encrypted_compute.require_active()?;
+        encrypted_compute.disclosures_verified = encrypted_compute.disclosures_verified.saturating_add(1);
+        encrypted_compute.updated_at = current_time;
+
+        Ok(())
+    }
+
+    // Issue a verifiable credential to `subject`. Only a commitment to the attributes is
+    // stored; the holder discloses the attributes themselves off-chain.
+    pub fn issue_verifiable_credential(ctx: Context<IssueVerifiableCredential>,
+                                      schema_id: String,
+                                      attribute_commitment: [u8; 32],
+                                      subject: Pubkey,
+                                      expires_at: i64) -> Result<()> {
+        let encrypted_compute_key = ctx.accounts.encrypted_compute.key();
+        let encrypted_compute = &mut ctx.accounts.encrypted_compute;
+
+        // Only the authority or a delegate with issue credential permission can issue
+        // credentials; a multisig-governed issuer doesn't vouch for anyone on a single signature
+        authorize_action(
+            encrypted_compute,
+            ctx.accounts.user.key,
+            PERMISSION_ISSUE_CREDENTIAL,
+            ctx.accounts.proposal.as_deref(),
+            ctx.accounts.proposer.as_ref(),
+            || MultisigAction::IssueCredential { schema_id: schema_id.clone(), attribute_commitment, subject, expires_at },
+        )?;
+
+        // Respect the account's time-lock policy
+        encrypted_compute.require_not_time_locked(LOCK_GUARD_CREDENTIAL)?;
+
+        // Validate schema id and attribute commitment are set
+        require!(!schema_id.is_empty(), ErrorCode::InvalidData);
+        require!(schema_id.len() <= MAX_SCHEMA_ID_LEN, ErrorCode::DataTooLong);
+        require!(attribute_commitment != [0u8; 32], ErrorCode::InvalidData);
+
+        let current_time = Clock::get()?.unix_timestamp;
+        // Credentials either never expire or expire in the future
+        require!(expires_at == 0 || expires_at > current_time, ErrorCode::InvalidData);
+
+        // Record the credential issuance; the list's issue count is the credential's index
+        encrypted_compute.require_active()?;
+        let index = ctx.accounts.revocation_list.next_credential_index()?;
+        encrypted_compute.credentials_issued = encrypted_compute.credentials_issued.saturating_add(1);
+        encrypted_compute.updated_at = current_time;
+
+        let credential = &mut ctx.accounts.credential;
+        credential.issuer = encrypted_compute_key;
+        credential.index = index;
+        credential.subject = subject;
+        credential.schema_id = schema_id;
+        credential.attribute_commitment = attribute_commitment;
+        credential.issued_at = current_time;
+        credential.expires_at = expires_at;
+        credential.bump = ctx.bumps.credential;
+
+        Ok(())
+    }
+
+    // Create the issuer's revocation list; it must exist before credentials are issued so
+    // verifiers can always look a credential up in it
+    pub fn initialize_revocation_list(ctx: Context<InitializeRevocationList>) -> Result<()> {
+        // Only the authority or a delegate with issue credential permission can set up revocation
+        authorize_action(
+            &ctx.accounts.encrypted_compute,
+            ctx.accounts.user.key,
+            PERMISSION_ISSUE_CREDENTIAL,
+            ctx.accounts.proposal.as_deref(),
+            ctx.accounts.proposer.as_ref(),
+            || MultisigAction::InitializeRevocationList,
+        )?;
+
+        let revocation_list = &mut ctx.accounts.revocation_list;
+        revocation_list.issuer = ctx.accounts.encrypted_compute.key();
+        revocation_list.credentials_issued = 0;
+        revocation_list.runs = Vec::new();
+        revocation_list.revoked_count = 0;
+        revocation_list.updated_at = Clock::get()?.unix_timestamp;
+        revocation_list.bump = ctx.bumps.revocation_list;
+
+        Ok(())
+    }
+
+    // Revoke a credential this account issued by setting its bit in the revocation list
+    pub fn revoke_credential(ctx: Context<RevokeCredential>, index: u32) -> Result<()> {
+        // Only the authority or a delegate with issue credential permission can revoke credentials
+        authorize_action(
+            &ctx.accounts.encrypted_compute,
+            ctx.accounts.user.key,
+            PERMISSION_ISSUE_CREDENTIAL,
+            ctx.accounts.proposal.as_deref(),
+            ctx.accounts.proposer.as_ref(),
+            || MultisigAction::RevokeCredential { index },
+        )?;
+
+        ctx.accounts.revocation_list.revoke(index, Clock::get()?.unix_timestamp)
+    }
+
+    // Reinstate a revoked credential
+    pub fn unrevoke_credential(ctx: Context<UnrevokeCredential>, index: u32) -> Result<()> {
+        // Only the authority or a delegate with issue credential permission can reinstate credentials
+        authorize_action(
+            &ctx.accounts.encrypted_compute,
+            ctx.accounts.user.key,
+            PERMISSION_ISSUE_CREDENTIAL,
+            ctx.accounts.proposal.as_deref(),
+            ctx.accounts.proposer.as_ref(),
+            || MultisigAction::UnrevokeCredential { index },
+        )?;
+
+        ctx.accounts.revocation_list.unrevoke(index, Clock::get()?.unix_timestamp)
+    }
+
+    // Prove to the calling program that the signer holds a live credential. Programs can
+    // also read the Credential and RevocationList accounts directly and call
+    // `Credential::require_valid`.
+    pub fn present_credential(ctx: Context<PresentCredential>) -> Result<()> {
+        ctx.accounts.credential.require_valid(
+            ctx.accounts.subject.key,
+            &ctx.accounts.revocation_list,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+}
+
+// Move pooled SPL stake out of the vault, signed by the executor registry
+fn transfer_from_stake_vault<'info>(
+    registry: &Account<'info, ExecutorRegistry>,
+    stake_vault: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let signer_seeds: &[&[&[u8]]] = &[&[b"executor_registry", &[registry.bump]]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            token::Transfer {
+                from: stake_vault.to_account_info(),
+                to: to.to_account_info(),
+                authority: registry.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )
+}
+
+// Authorize a privileged instruction. Single-authority accounts need the signer to hold
+// `permission`. Multisig-governed ones need one of the multisig's signers to bring the
+// approved proposal for exactly this `action`, which is used up: it is closed and its rent
+// returned to the proposer.
+fn authorize_action<'info>(
+    encrypted_compute: &EncryptedCompute,
+    signer: &Pubkey,
+    permission: u8,
+    proposal: Option<&Account<'info, MultisigProposal>>,
+    proposer: Option<&SystemAccount<'info>>,
+    action: impl FnOnce() -> MultisigAction,
+) -> Result<()> {
+    if encrypted_compute.multisig.is_none() {
+        return encrypted_compute.require_permission(signer, permission);
+    }
+
+    encrypted_compute.multisig_signer_index(signer)?;
+    let (Some(proposal), Some(proposer)) = (proposal, proposer) else {
+        return err!(ErrorCode::MultisigRequired);
+    };
+    require!(proposer.key() == proposal.proposer, ErrorCode::Unauthorized);
+    encrypted_compute.require_approved_action(proposal, &action())?;
+
+    proposal.close(proposer.to_account_info())
+}
+
+// Move SPL fees out of a request's vault, signed by its fee escrow
+fn transfer_from_fee_vault<'info>(
+    fee_escrow: &Account<'info, FeeEscrow>,
+    fee_vault: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let signer_seeds: &[&[&[u8]]] = &[&[b"fee_escrow", fee_escrow.request.as_ref(), &[fee_escrow.bump]]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            token::Transfer {
+                from: fee_vault.to_account_info(),
+                to: to.to_account_info(),
+                authority: fee_escrow.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )
+}
+
+// Close a fee vault, sweeping whatever it still holds to the requester's token account
+// first since the token program only closes empty accounts, and returning its rent
+fn close_fee_vault<'info>(
+    fee_escrow: &Account<'info, FeeEscrow>,
+    fee_vault: &Account<'info, TokenAccount>,
+    requester_token_account: &Account<'info, TokenAccount>,
+    destination: &impl ToAccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    transfer_from_fee_vault(fee_escrow, fee_vault, requester_token_account, token_program, fee_vault.amount)?;
+
+    let signer_seeds: &[&[&[u8]]] = &[&[b"fee_escrow", fee_escrow.request.as_ref(), &[fee_escrow.bump]]];
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token::CloseAccount {
+            account: fee_vault.to_account_info(),
+            destination: destination.to_account_info(),
+            authority: fee_escrow.to_account_info(),
+        },
+        signer_seeds,
+    ))
+}
+
+#[derive(Accounts)]
+#[instruction(data_hash: [u8; 32])]
+pub struct InitializeEncryptedCompute<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = EncryptedCompute::LEN,
+        seeds = [b"encrypted_compute", user.key().as_ref(), data_hash.as_ref()],
+        bump
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateEncryptedCompute<'info> {
+    /// CHECK: Still in an older layout, so it is decoded and its seeds verified by hand
+    #[account(mut, owner = crate::ID)]
+    pub encrypted_compute: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateEncryptedData<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BeginCiphertextUpload<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u32)]
+pub struct AppendCiphertextChunk<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        init,
+        payer = user,
+        space = CiphertextChunk::LEN,
+        seeds = [b"ciphertext_chunk", encrypted_compute.key().as_ref(), &encrypted_compute.ciphertext_generation.to_le_bytes(), &index.to_le_bytes()],
+        bump
+    )]
+    pub chunk: Account<'info, CiphertextChunk>,
+    // Approved proposal for this call, required once the account is multisig-governed
+    #[account(
+        mut,
+        seeds = [b"multisig_proposal", encrypted_compute.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Option<Box<Account<'info, MultisigProposal>>>,
+    #[account(mut)]
+    pub proposer: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeCiphertext<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    // Approved proposal for this call, required once the account is multisig-governed
+    #[account(
+        mut,
+        seeds = [b"multisig_proposal", encrypted_compute.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Option<Box<Account<'info, MultisigProposal>>>,
+    #[account(mut)]
+    pub proposer: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetExternalCiphertext<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCiphertextChunk<'info> {
+    #[account(
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        mut,
+        seeds = [b"ciphertext_chunk", encrypted_compute.key().as_ref(), &chunk.generation.to_le_bytes(), &chunk.index.to_le_bytes()],
+        bump = chunk.bump,
+        close = user
+    )]
+    pub chunk: Account<'info, CiphertextChunk>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct QueueComputation<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        seeds = [b"computation_definition", definition.definition_id.as_bytes(), &definition.version.to_le_bytes()],
+        bump = definition.bump,
+    )]
+    pub definition: Box<Account<'info, ComputationDefinition>>,
+    #[account(
+        init,
+        payer = user,
+        space = ComputationRequest::LEN,
+        seeds = [b"computation_request", encrypted_compute.key().as_ref(), &encrypted_compute.computations_queued.to_le_bytes()],
+        bump
+    )]
+    pub computation_request: Account<'info, ComputationRequest>,
+    #[account(
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+    )]
+    pub fee_config: Box<Account<'info, FeeConfig>>,
+    #[account(
+        init,
+        payer = user,
+        space = FeeEscrow::LEN,
+        seeds = [b"fee_escrow", computation_request.key().as_ref()],
+        bump
+    )]
+    pub fee_escrow: Account<'info, FeeEscrow>,
+    // SPL fees only: the mint, the vault created for this request and the payer's account
+    pub fee_mint: Option<Account<'info, Mint>>,
+    #[account(
+        init,
+        payer = user,
+        token::mint = fee_mint,
+        token::authority = fee_escrow,
+        seeds = [b"fee_vault", computation_request.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub requester_token_account: Option<Account<'info, TokenAccount>>,
+    // Approved proposal for this call, required once the account is multisig-governed
+    #[account(
+        mut,
+        seeds = [b"multisig_proposal", encrypted_compute.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Option<Box<Account<'info, MultisigProposal>>>,
+    #[account(mut)]
+    pub proposer: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimComputation<'info> {
+    #[account(
+        mut,
+        seeds = [b"computation_request", computation_request.encrypted_compute.as_ref(), &computation_request.request_id.to_le_bytes()],
+        bump = computation_request.bump,
+    )]
+    pub computation_request: Account<'info, ComputationRequest>,
+    #[account(
+        seeds = [b"computation_definition", computation_request.definition_id.as_bytes(), &computation_request.definition_version.to_le_bytes()],
+        bump = definition.bump,
+    )]
+    pub definition: Box<Account<'info, ComputationDefinition>>,
+    #[account(
+        seeds = [b"executor_registry"],
+        bump = executor_registry.bump,
+    )]
+    pub executor_registry: Box<Account<'info, ExecutorRegistry>>,
+    #[account(
+        seeds = [b"executor_node", executor.key().as_ref()],
+        bump = executor_node.bump,
+    )]
+    pub executor_node: Account<'info, ExecutorNode>,
+    pub executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitComputationResult<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        mut,
+        seeds = [b"computation_request", encrypted_compute.key().as_ref(), &computation_request.request_id.to_le_bytes()],
+        bump = computation_request.bump,
+    )]
+    pub computation_request: Account<'info, ComputationRequest>,
+    #[account(
+        seeds = [b"computation_definition", computation_request.definition_id.as_bytes(), &computation_request.definition_version.to_le_bytes()],
+        bump = definition.bump,
+    )]
+    pub definition: Box<Account<'info, ComputationDefinition>>,
+    #[account(
+        seeds = [b"executor_registry"],
+        bump = executor_registry.bump,
+    )]
+    pub executor_registry: Box<Account<'info, ExecutorRegistry>>,
+    #[account(
+        mut,
+        seeds = [b"executor_node", executor.key().as_ref()],
+        bump = executor_node.bump,
+    )]
+    pub executor_node: Account<'info, ExecutorNode>,
+    #[account(
+        mut,
+        seeds = [b"fee_escrow", computation_request.key().as_ref()],
+        bump = fee_escrow.bump,
+    )]
+    pub fee_escrow: Account<'info, FeeEscrow>,
+    #[account(mut, address = fee_escrow.treasury)]
+    pub treasury: Option<SystemAccount<'info>>,
+    // SPL fees only: the request's vault and the payees' token accounts
+    #[account(
+        mut,
+        seeds = [b"fee_vault", computation_request.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = executor_token_account.owner == executor.key() @ ErrorCode::InvalidFeeAccounts,
+    )]
+    pub executor_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == fee_escrow.treasury @ ErrorCode::InvalidFeeAccounts,
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub executor: Signer<'info>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// The encrypted compute account is not required so a request can be refunded even if
+// its account has since been closed
+#[derive(Accounts)]
+pub struct RefundTimedOutComputation<'info> {
+    #[account(
+        mut,
+        seeds = [b"computation_request", computation_request.encrypted_compute.as_ref(), &computation_request.request_id.to_le_bytes()],
+        bump = computation_request.bump,
+        close = requester
+    )]
+    pub computation_request: Account<'info, ComputationRequest>,
+    #[account(
+        mut,
+        seeds = [b"fee_escrow", computation_request.key().as_ref()],
+        bump = fee_escrow.bump,
+        close = requester
+    )]
+    pub fee_escrow: Account<'info, FeeEscrow>,
+    #[account(mut, address = computation_request.requester)]
+    pub requester: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault", computation_request.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = requester_token_account.owner == computation_request.requester @ ErrorCode::InvalidFeeAccounts,
+    )]
+    pub requester_token_account: Option<Account<'info, TokenAccount>>,
+    pub user: Signer<'info>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseComputationRequest<'info> {
+    #[account(
+        mut,
+        seeds = [b"computation_request", computation_request.encrypted_compute.as_ref(), &computation_request.request_id.to_le_bytes()],
+        bump = computation_request.bump,
+        close = user
+    )]
+    pub computation_request: Account<'info, ComputationRequest>,
+    #[account(
+        mut,
+        seeds = [b"fee_escrow", computation_request.key().as_ref()],
+        bump = fee_escrow.bump,
+        close = user
+    )]
+    pub fee_escrow: Account<'info, FeeEscrow>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault", computation_request.key().as_ref()],
+        bump,
+    )]
+    pub fee_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = requester_token_account.owner == computation_request.requester @ ErrorCode::InvalidFeeAccounts,
+    )]
+    pub requester_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeeConfig<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = FeeConfig::LEN,
+        seeds = [b"fee_config"],
+        bump
+    )]
+    pub fee_config: Account<'info, FeeConfig>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    // Only the program's upgrade authority can create the singleton
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, program::ArciumEncryptedCompute>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(user.key()) @ ErrorCode::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+pub struct ManageFeeConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"fee_config"],
+        bump = fee_config.bump,
+    )]
+    pub fee_config: Account<'info, FeeConfig>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeExecutorRegistry<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = ExecutorRegistry::LEN,
+        seeds = [b"executor_registry"],
+        bump
+    )]
+    pub registry: Account<'info, ExecutorRegistry>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    // Only the program's upgrade authority can create the singleton
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, program::ArciumEncryptedCompute>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(user.key()) @ ErrorCode::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeExecutorStakeVault<'info> {
+    #[account(
+        seeds = [b"executor_registry"],
+        bump = registry.bump,
+        constraint = registry.stake_mint == Some(stake_mint.key()) @ ErrorCode::InvalidStakeAccounts,
+    )]
+    pub registry: Account<'info, ExecutorRegistry>,
+    pub stake_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = user,
+        token::mint = stake_mint,
+        token::authority = registry,
+        seeds = [b"executor_stake_vault"],
+        bump
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterExecutor<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = ExecutorNode::LEN,
+        seeds = [b"executor_node", user.key().as_ref()],
+        bump
+    )]
+    pub executor_node: Account<'info, ExecutorNode>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Token accounts are only needed when the registry stakes an SPL mint
+#[derive(Accounts)]
+pub struct StakeExecutor<'info> {
+    #[account(
+        seeds = [b"executor_registry"],
+        bump = registry.bump,
+    )]
+    pub registry: Account<'info, ExecutorRegistry>,
+    #[account(
+        mut,
+        seeds = [b"executor_node", user.key().as_ref()],
+        bump = executor_node.bump,
+    )]
+    pub executor_node: Account<'info, ExecutorNode>,
+    #[account(
+        mut,
+        seeds = [b"executor_stake_vault"],
+        bump,
+    )]
+    pub stake_vault: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub staker_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeregisterExecutor<'info> {
+    #[account(
+        mut,
+        seeds = [b"executor_node", user.key().as_ref()],
+        bump = executor_node.bump,
+    )]
+    pub executor_node: Account<'info, ExecutorNode>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawExecutorStake<'info> {
+    #[account(
+        seeds = [b"executor_registry"],
+        bump = registry.bump,
+    )]
+    pub registry: Account<'info, ExecutorRegistry>,
+    #[account(
+        mut,
+        seeds = [b"executor_node", user.key().as_ref()],
+        bump = executor_node.bump,
+        close = user
+    )]
+    pub executor_node: Account<'info, ExecutorNode>,
+    #[account(
+        mut,
+        seeds = [b"executor_stake_vault"],
+        bump,
+    )]
+    pub stake_vault: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = staker_token_account.owner == user.key() @ ErrorCode::InvalidStakeAccounts,
+    )]
+    pub staker_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct SlashExecutor<'info> {
+    #[account(
+        seeds = [b"executor_registry"],
+        bump = registry.bump,
+    )]
+    pub registry: Account<'info, ExecutorRegistry>,
+    #[account(
+        mut,
+        seeds = [b"executor_node", executor_node.authority.as_ref()],
+        bump = executor_node.bump,
+    )]
+    pub executor_node: Account<'info, ExecutorNode>,
+    // The request the node is slashed over, and the definition and key its result is checked against
+    #[account(
+        mut,
+        seeds = [b"computation_request", computation_request.encrypted_compute.as_ref(), &computation_request.request_id.to_le_bytes()],
+        bump = computation_request.bump,
+    )]
+    pub computation_request: Account<'info, ComputationRequest>,
+    #[account(
+        seeds = [b"computation_definition", computation_request.definition_id.as_bytes(), &computation_request.definition_version.to_le_bytes()],
+        bump = definition.bump,
+    )]
+    pub definition: Box<Account<'info, ComputationDefinition>>,
+    #[account(
+        seeds = [b"verifying_key", verifying_key.circuit_id.as_bytes(), &verifying_key.version.to_le_bytes()],
+        bump = verifying_key.bump,
+    )]
+    pub verifying_key: Option<Box<Account<'info, VerifyingKey>>>,
+    #[account(
+        mut,
+        seeds = [b"executor_stake_vault"],
+        bump,
+    )]
+    pub stake_vault: Option<Account<'info, TokenAccount>>,
+    #[account(mut, address = registry.treasury)]
+    pub treasury: Option<SystemAccount<'info>>,
+    #[account(
+        mut,
+        token::authority = registry.treasury,
+        constraint = Some(treasury_token_account.mint) == registry.stake_mint @ ErrorCode::InvalidStakeAccounts,
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyTimeLock<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeTimeLock<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CommitTimeLock<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendTimeLock<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ShortenTimeLock<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CheckTimeLockStatus<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetUnlockPolicy<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RecordUnlockCondition<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    // Only needed for verified-proof conditions
+    pub zk_proof_record: Option<Account<'info, ZkProofRecord>>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetTimeLockGuards<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ManagePermissions<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAuthorityTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthorityTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuthorityTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseEncryptedCompute<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+        close = user
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EnableMultisig<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeMultisigAction<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        init,
+        payer = user,
+        space = MultisigProposal::LEN,
+        seeds = [b"multisig_proposal", encrypted_compute.key().as_ref(), &encrypted_compute.multisig_proposals_created.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, MultisigProposal>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveMultisigAction<'info> {
+    #[account(
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        mut,
+        seeds = [b"multisig_proposal", encrypted_compute.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, MultisigProposal>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteMultisigAction<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        mut,
+        seeds = [b"multisig_proposal", encrypted_compute.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        close = proposer
+    )]
+    pub proposal: Account<'info, MultisigProposal>,
+    #[account(mut, address = proposal.proposer)]
+    pub proposer: SystemAccount<'info>,
+    #[account(mut, address = encrypted_compute.authority)]
+    pub authority: SystemAccount<'info>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelMultisigAction<'info> {
+    #[account(
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        mut,
+        seeds = [b"multisig_proposal", encrypted_compute.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        close = user
+    )]
+    pub proposal: Account<'info, MultisigProposal>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeVerifierRegistry<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = VerifierRegistry::LEN,
+        seeds = [b"verifier_registry"],
+        bump
+    )]
+    pub registry: Account<'info, VerifierRegistry>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    // Only the program's upgrade authority can create the singleton
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, program::ArciumEncryptedCompute>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(user.key()) @ ErrorCode::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+#[instruction(circuit_id: String)]
+pub struct RegisterVerifyingKey<'info> {
+    #[account(
+        seeds = [b"verifier_registry"],
+        bump = registry.bump,
+    )]
+    pub registry: Account<'info, VerifierRegistry>,
+    #[account(
+        init,
+        payer = user,
+        space = VerifyingKey::LEN,
+        seeds = [b"verifying_key", circuit_id.as_bytes(), &1u32.to_le_bytes()],
+        bump
+    )]
+    pub verifying_key: Account<'info, VerifyingKey>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RotateVerifyingKey<'info> {
+    #[account(
+        seeds = [b"verifier_registry"],
+        bump = registry.bump,
+    )]
+    pub registry: Account<'info, VerifierRegistry>,
+    #[account(
+        mut,
+        seeds = [b"verifying_key", current_key.circuit_id.as_bytes(), &current_key.version.to_le_bytes()],
+        bump = current_key.bump,
+    )]
+    pub current_key: Account<'info, VerifyingKey>,
+    #[account(
+        init,
+        payer = user,
+        space = VerifyingKey::LEN,
+        seeds = [b"verifying_key", current_key.circuit_id.as_bytes(), &(current_key.version + 1).to_le_bytes()],
+        bump
+    )]
+    pub next_key: Account<'info, VerifyingKey>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeprecateVerifyingKey<'info> {
+    #[account(
+        seeds = [b"verifier_registry"],
+        bump = registry.bump,
+    )]
+    pub registry: Account<'info, VerifierRegistry>,
+    #[account(
+        mut,
+        seeds = [b"verifying_key", verifying_key.circuit_id.as_bytes(), &verifying_key.version.to_le_bytes()],
+        bump = verifying_key.bump,
+    )]
+    pub verifying_key: Account<'info, VerifyingKey>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeDefinitionRegistry<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = DefinitionRegistry::LEN,
+        seeds = [b"definition_registry"],
+        bump
+    )]
+    pub registry: Account<'info, DefinitionRegistry>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    // Only the program's upgrade authority can create the singleton
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, program::ArciumEncryptedCompute>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(user.key()) @ ErrorCode::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+#[instruction(definition_id: String)]
+pub struct RegisterComputationDefinition<'info> {
+    #[account(
+        seeds = [b"definition_registry"],
+        bump = registry.bump,
+    )]
+    pub registry: Account<'info, DefinitionRegistry>,
+    #[account(
+        init,
+        payer = user,
+        space = ComputationDefinition::LEN,
+        seeds = [b"computation_definition", definition_id.as_bytes(), &1u32.to_le_bytes()],
+        bump
+    )]
+    pub definition: Account<'info, ComputationDefinition>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RotateComputationDefinition<'info> {
+    #[account(
+        seeds = [b"definition_registry"],
+        bump = registry.bump,
+    )]
+    pub registry: Account<'info, DefinitionRegistry>,
+    #[account(
+        mut,
+        seeds = [b"computation_definition", current_definition.definition_id.as_bytes(), &current_definition.version.to_le_bytes()],
+        bump = current_definition.bump,
+    )]
+    pub current_definition: Account<'info, ComputationDefinition>,
+    #[account(
+        init,
+        payer = user,
+        space = ComputationDefinition::LEN,
+        seeds = [b"computation_definition", current_definition.definition_id.as_bytes(), &(current_definition.version + 1).to_le_bytes()],
+        bump
+    )]
+    pub next_definition: Account<'info, ComputationDefinition>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeprecateComputationDefinition<'info> {
+    #[account(
+        seeds = [b"definition_registry"],
+        bump = registry.bump,
+    )]
+    pub registry: Account<'info, DefinitionRegistry>,
+    #[account(
+        mut,
+        seeds = [b"computation_definition", definition.definition_id.as_bytes(), &definition.version.to_le_bytes()],
+        bump = definition.bump,
+    )]
+    pub definition: Account<'info, ComputationDefinition>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyZKProof<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        seeds = [b"verifying_key", verifying_key.circuit_id.as_bytes(), &verifying_key.version.to_le_bytes()],
+        bump = verifying_key.bump,
+    )]
+    pub verifying_key: Account<'info, VerifyingKey>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(proof_id: String)]
+pub struct StoreZKProofData<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        seeds = [b"verifying_key", verifying_key.circuit_id.as_bytes(), &verifying_key.version.to_le_bytes()],
+        bump = verifying_key.bump,
+    )]
+    pub verifying_key: Account<'info, VerifyingKey>,
+    #[account(
+        init,
+        payer = user,
+        space = ZkProofRecord::LEN,
+        seeds = [b"zk_proof", encrypted_compute.key().as_ref(), proof_id.as_bytes()],
+        bump
+    )]
+    pub zk_proof_record: Account<'info, ZkProofRecord>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyStoredZKProof<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        seeds = [b"verifying_key", verifying_key.circuit_id.as_bytes(), &verifying_key.version.to_le_bytes()],
+        bump = verifying_key.bump,
+    )]
+    pub verifying_key: Account<'info, VerifyingKey>,
+    #[account(
+        mut,
+        seeds = [b"zk_proof", encrypted_compute.key().as_ref(), zk_proof_record.proof_id.as_bytes()],
+        bump = zk_proof_record.bump,
+    )]
+    pub zk_proof_record: Account<'info, ZkProofRecord>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseZKProofRecord<'info> {
+    #[account(
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        mut,
+        seeds = [b"zk_proof", encrypted_compute.key().as_ref(), zk_proof_record.proof_id.as_bytes()],
+        bump = zk_proof_record.bump,
+        close = submitter
+    )]
+    pub zk_proof_record: Account<'info, ZkProofRecord>,
+    #[account(mut, address = zk_proof_record.submitter)]
+    pub submitter: SystemAccount<'info>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenAuditLogPage<'info> {
+    #[account(
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        seeds = [b"audit_trail", encrypted_compute.key().as_ref(), audit_trail.name.as_bytes()],
+        bump = audit_trail.bump,
+    )]
+    pub audit_trail: Account<'info, AuditTrail>,
+    #[account(
+        mut,
+        seeds = [b"audit_log", audit_trail.key().as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+    #[account(
+        init,
+        payer = user,
+        space = AuditLogPage::LEN,
+        seeds = [b"audit_log_page", audit_log.key().as_ref(), &audit_log.page_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_log_page: Account<'info, AuditLogPage>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LogAuditEvent<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        seeds = [b"audit_trail", encrypted_compute.key().as_ref(), audit_trail.name.as_bytes()],
+        bump = audit_trail.bump,
+    )]
+    pub audit_trail: Account<'info, AuditTrail>,
+    #[account(
+        mut,
+        seeds = [b"audit_log", audit_trail.key().as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+    #[account(
+        mut,
+        seeds = [b"audit_log_page", audit_log.key().as_ref(), &audit_log.current_page().to_le_bytes()],
+        bump = audit_log_page.bump,
+    )]
+    pub audit_log_page: Account<'info, AuditLogPage>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyAuditLogChain<'info> {
+    #[account(
+        seeds = [b"audit_log", audit_log.audit_trail.as_ref()],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+}
+
+#[derive(Accounts)]
+#[instruction(trail_name: String)]
+pub struct CreateAuditTrail<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        init,
+        payer = user,
+        space = AuditTrail::LEN,
+        seeds = [b"audit_trail", encrypted_compute.key().as_ref(), trail_name.as_bytes()],
+        bump
+    )]
+    pub audit_trail: Account<'info, AuditTrail>,
+    #[account(
+        init,
+        payer = user,
+        space = AuditLog::LEN,
+        seeds = [b"audit_log", audit_trail.key().as_ref()],
+        bump
+    )]
+    pub audit_log: Account<'info, AuditLog>,
+    #[account(
+        init,
+        payer = user,
+        space = AuditLogPage::LEN,
+        seeds = [b"audit_log_page", audit_log.key().as_ref(), &0u32.to_le_bytes()],
+        bump
+    )]
+    pub audit_log_page: Account<'info, AuditLogPage>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageAuditTrail<'info> {
+    #[account(
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        mut,
+        seeds = [b"audit_trail", encrypted_compute.key().as_ref(), audit_trail.name.as_bytes()],
+        bump = audit_trail.bump,
+    )]
+    pub audit_trail: Account<'info, AuditTrail>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AuthorizeAuditTrailRead<'info> {
+    #[account(
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        seeds = [b"audit_trail", encrypted_compute.key().as_ref(), audit_trail.name.as_bytes()],
+        bump = audit_trail.bump,
+    )]
+    pub audit_trail: Account<'info, AuditTrail>,
+    pub reader: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifySelectiveDisclosure<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        seeds = [b"credential", credential.issuer.as_ref(), &credential.index.to_le_bytes()],
+        bump = credential.bump,
+    )]
+    pub credential: Account<'info, Credential>,
+    #[account(
+        seeds = [b"revocation_list", credential.issuer.as_ref()],
+        bump = revocation_list.bump,
+    )]
+    pub revocation_list: Account<'info, RevocationList>,
+    #[account(
+        seeds = [b"verifying_key", verifying_key.circuit_id.as_bytes(), &verifying_key.version.to_le_bytes()],
+        bump = verifying_key.bump,
+    )]
+    pub verifying_key: Account<'info, VerifyingKey>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct IssueVerifiableCredential<'info> {
+    #[account(
+        mut,
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    // Holds the issue count, so it must load before the credential it seeds
+    #[account(
+        mut,
+        seeds = [b"revocation_list", encrypted_compute.key().as_ref()],
+        bump = revocation_list.bump,
+    )]
+    pub revocation_list: Account<'info, RevocationList>,
+    #[account(
+        init,
+        payer = user,
+        space = Credential::LEN,
+        seeds = [b"credential", encrypted_compute.key().as_ref(), &revocation_list.credentials_issued.to_le_bytes()],
+        bump
+    )]
+    pub credential: Account<'info, Credential>,
+    // Approved proposal for this call, required once the account is multisig-governed
+    #[account(
+        mut,
+        seeds = [b"multisig_proposal", encrypted_compute.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Option<Box<Account<'info, MultisigProposal>>>,
+    #[account(mut)]
+    pub proposer: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRevocationList<'info> {
+    #[account(
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    #[account(
+        init,
+        payer = user,
+        space = RevocationList::space(0),
+        seeds = [b"revocation_list", encrypted_compute.key().as_ref()],
+        bump
+    )]
+    pub revocation_list: Account<'info, RevocationList>,
+    // Approved proposal for this call, required once the account is multisig-governed
+    #[account(
+        mut,
+        seeds = [b"multisig_proposal", encrypted_compute.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Option<Box<Account<'info, MultisigProposal>>>,
+    #[account(mut)]
+    pub proposer: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeCredential<'info> {
+    #[account(
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
+        bump,
+    )]
+    pub encrypted_compute: Account<'info, EncryptedCompute>,
+    // Revoking can start a new run
+    #[account(
+        mut,
+        seeds = [b"revocation_list", encrypted_compute.key().as_ref()],
+        bump = revocation_list.bump,
+        realloc = revocation_list.space_for_change(),
+        realloc::payer = user,
+        realloc::zero = false,
+    )]
+    pub revocation_list: Account<'info, RevocationList>,
+    // Approved proposal for this call, required once the account is multisig-governed
+    #[account(
+        mut,
+        seeds = [b"multisig_proposal", encrypted_compute.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Option<Box<Account<'info, MultisigProposal>>>,
+    #[account(mut)]
+    pub proposer: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnrevokeCredential<'info> {
+    #[account(
+        seeds = [b"encrypted_compute", encrypted_compute.creator.as_ref(), encrypted_compute.address_seed()],
         bump,
     )]
     pub encrypted_compute: Account<'info, EncryptedCompute>,
+    // Reinstating a credential in the middle of a run splits it in two
+    #[account(
+        mut,
+        seeds = [b"revocation_list", encrypted_compute.key().as_ref()],
+        bump = revocation_list.bump,
+        realloc = revocation_list.space_for_change(),
+        realloc::payer = user,
+        realloc::zero = false,
+    )]
+    pub revocation_list: Account<'info, RevocationList>,
+    // Approved proposal for this call, required once the account is multisig-governed
+    #[account(
+        mut,
+        seeds = [b"multisig_proposal", encrypted_compute.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Option<Box<Account<'info, MultisigProposal>>>,
+    #[account(mut)]
+    pub proposer: Option<SystemAccount<'info>>,
     #[account(mut)]
     pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PresentCredential<'info> {
+    #[account(
+        seeds = [b"credential", credential.issuer.as_ref(), &credential.index.to_le_bytes()],
+        bump = credential.bump,
+    )]
+    pub credential: Account<'info, Credential>,
+    #[account(
+        seeds = [b"revocation_list", credential.issuer.as_ref()],
+        bump = revocation_list.bump,
+    )]
+    pub revocation_list: Account<'info, RevocationList>,
+    pub subject: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct EncryptedCompute {
+    pub version: u8,                // Layout version; see migration.rs for the older one
+    pub authority: Pubkey,           // The owner of this account
+    pub creator: Pubkey,             // The original owner; part of the PDA seeds so they survive transfers
+    pub pending_authority: Option<Pubkey>, // Proposed new owner awaiting acceptance
+    pub data_hash: [u8; 32],        // Digest of the original data
+    pub hash_algorithm: DigestAlgorithm, // How data_hash was computed
+    #[max_len(MAX_LEGACY_SEED_LEN)]
+    pub legacy_seed: Vec<u8>,       // String seed of accounts created before data_hash was binary; empty otherwise
+    // encrypted_data: Storing large encrypted data directly on-chain is expensive and limited;
+    // `storage` records whether the ciphertext is here, in chunk accounts or off-chain.
+    #[max_len(MAX_ENCRYPTED_DATA_LEN)]
+    pub encrypted_data: Vec<u8>,    // Encrypted data stored on-chain
+    pub ciphertext_metadata: Option<CiphertextMetadata>, // How the inline or chunked ciphertext was encrypted
+    pub storage: CiphertextStorage, // Where the ciphertext lives
+    pub ciphertext_generation: u32, // Upload the newest chunks belong to; part of their seeds
+    pub ciphertext_upload_open: bool, // Whether chunks are being staged
+    pub pending_chunks: u32,        // Chunks staged so far in the open upload
+    pub status: EncryptedComputeStatus,             // Current lifecycle status
+    pub created_at: i64,            // Timestamp when account was created
+    pub updated_at: i64,            // Timestamp when account was last updated
+    pub time_lock_expiration: i64,  // Timestamp when data becomes available (0 if no time lock)
+    pub is_locked: bool,            // Whether the data is currently locked
+    pub unlock_conditions_met: bool,// Whether unlock conditions have been met
+    pub time_lock_committed: bool,  // Whether the current lock may only be extended
+    pub lock_guards: u16,           // LOCK_GUARD_* bits of operations blocked while locked
+    pub unlock_policy: Option<UnlockPolicy>, // Conditions that release a lock before it expires
+    #[max_len(MAX_DELEGATES)]
+    pub delegates: Vec<Delegate>,   // Keys allowed to act on the authority's behalf
+    pub multisig: Option<AuthorityMultisig>, // M-of-N signers governing privileged actions, if enabled
+    pub multisig_nonce: u32,        // Bumped on every multisig change so stale proposals can't run
+    pub multisig_proposals_created: u32, // Number of multisig proposals ever created; seeds the next one
+    pub time_locks_applied: u32,    // Number of time-locks ever applied
+    pub computations_queued: u32,   // Number of computations ever queued; seeds the next request
+    pub computations_completed: u32,// Number of computations completed by an executor
+    pub proofs_stored: u32,         // Number of ZK proofs stored
+    pub proofs_verified: u32,       // Number of ZK proofs verified
+    pub audit_trails_created: u32,  // Number of audit trails created
+    pub audit_events_logged: u64,   // Number of audit events logged
+    pub disclosures_verified: u32,  // Number of selective disclosures verified
+    pub credentials_issued: u32,    // Number of credentials issued
+}
+
+// Layout version of EncryptedCompute accounts written by this program; the unversioned
+// devnet layout counts as 0
+pub const ENCRYPTED_COMPUTE_VERSION: u8 = 1;
+
+impl EncryptedCompute {
+    // discriminator + fields at their #[max_len] bounds
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+}
+
+// Maximum length of a definition id; it is used as a PDA seed so must fit in 32 bytes
+pub const MAX_DEFINITION_ID_LEN: usize = 32;
+// Computation requests are bounded so every request has a fixed maximum size
+pub const MAX_COMPUTATION_INPUTS: usize = 8;
+pub const MAX_COMPUTATION_OUTPUTS: usize = 4;
+// Combined size of all outputs of one request
+pub const MAX_COMPUTATION_OUTPUT_LEN: usize = 1024;
+pub const MAX_ALLOWED_EXECUTORS: usize = 8;
+// Longest a request may wait for an executor before it can be refunded (7 days)
+pub const MAX_COMPUTATION_TIMEOUT: i64 = 7 * 24 * 60 * 60;
+// How long a timed-out claim or a proven result stays open to slashing before the
+// request can be closed (3 days)
+pub const SLASHING_WINDOW: i64 = 3 * 24 * 60 * 60;
+
+#[account]
+pub struct ComputationRequest {
+    pub encrypted_compute: Pubkey,   // The account the computation was queued on
+    pub request_id: u32,            // Sequence number, part of the PDA seeds
+    pub requester: Pubkey,          // Who queued the computation and paid the fee
+    pub definition_id: String,      // Computation to run
+    pub definition_version: u32,    // Definition version the request was checked against
+    pub inputs: Vec<Pubkey>,        // Encrypted compute accounts read as inputs, this account first
+    pub fee: u64,                   // Fee held in escrow for the executor
+    pub fee_mint: Option<Pubkey>,   // SPL mint the fee is paid in (None = SOL)
+    pub status: ComputationStatus,  // Where the request is in its lifecycle
+    pub assigned_executor: Option<Pubkey>, // Executor that claimed the request, if any
+    pub executor: Option<Pubkey>,   // Who submitted the result
+    pub encrypted_outputs: Vec<Vec<u8>>, // Result ciphertexts, written on completion
+    pub compute_cost: u64,          // Cost reported by the executor
+    pub result_proof: Option<[u8; groth16::PROOF_LEN]>, // Proof of the result, for definitions with a result circuit
+    pub callback: Option<ComputationCallback>, // Consumer program invoked on completion
+    pub queued_at: i64,             // Timestamp when the request was queued
+    pub timeout_at: i64,            // Timestamp after which the fee can be refunded
+    pub completed_at: i64,          // Timestamp when the result was submitted (0 if pending)
+    pub slashed: bool,              // Whether the executor has been slashed over this request
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl ComputationRequest {
+    // discriminator + encrypted_compute + request_id + requester + definition_id + version
+    // + inputs + fee + fee_mint + status + executors + outputs + compute_cost + result_proof
+    // + callback + timestamps + slashed + bump
+    pub const LEN: usize = 8
+        + 32
+        + 4
+        + 32
+        + 4 + MAX_DEFINITION_ID_LEN
+        + 4
+        + 4 + MAX_COMPUTATION_INPUTS * 32
+        + 8
+        + 1 + 32
+        + 1
+        + (1 + 32) * 2
+        + 4 + MAX_COMPUTATION_OUTPUTS * 4 + MAX_COMPUTATION_OUTPUT_LEN
+        + 8
+        + 1 + groth16::PROOF_LEN
+        + ComputationCallback::MAX_LEN
+        + 8 * 3
+        + 1
+        + 1;
+
+    pub fn claim(&mut self, definition: &ComputationDefinition, executor: Pubkey, now: i64) -> Result<()> {
+        require!(
+            self.status == ComputationStatus::Queued,
+            ErrorCode::ComputationNotPending
+        );
+        require!(now < self.timeout_at, ErrorCode::ComputationTimedOut);
+        require!(self.assigned_executor.is_none(), ErrorCode::ComputationAlreadyClaimed);
+        require!(definition.spec.allows_executor(&executor), ErrorCode::Unauthorized);
+
+        self.assigned_executor = Some(executor);
+        Ok(())
+    }
+
+    pub fn complete(&mut self,
+                    definition: &ComputationDefinition,
+                    executor: Pubkey,
+                    encrypted_outputs: Vec<Vec<u8>>,
+                    compute_cost: u64,
+                    result_proof: Option<[u8; groth16::PROOF_LEN]>,
+                    now: i64) -> Result<()> {
+        require!(
+            self.status == ComputationStatus::Queued,
+            ErrorCode::ComputationNotPending
+        );
+        require!(now < self.timeout_at, ErrorCode::ComputationTimedOut);
+        require!(
+            definition.definition_id == self.definition_id && definition.version == self.definition_version,
+            ErrorCode::InvalidComputationDefinition
+        );
+        require!(definition.spec.allows_executor(&executor), ErrorCode::Unauthorized);
+        // A claimed request is reserved for the executor that claimed it
+        require!(
+            self.assigned_executor.is_none() || self.assigned_executor == Some(executor),
+            ErrorCode::Unauthorized
+        );
+        require!(
+            result_proof.is_some() == definition.spec.result_circuit.is_some(),
+            ErrorCode::InvalidZKProof
+        );
+
+        // Outputs must match the definition's shape and budget
+        require!(
+            encrypted_outputs.len() == definition.spec.output_arity as usize,
+            ErrorCode::ComputationArityMismatch
+        );
+        require!(
+            encrypted_outputs.iter().all(|output| !output.is_empty()),
+            ErrorCode::InvalidData
+        );
+        require!(
+            encrypted_outputs.iter().map(Vec::len).sum::<usize>() <= MAX_COMPUTATION_OUTPUT_LEN,
+            ErrorCode::DataTooLong
+        );
+        require!(
+            compute_cost <= definition.spec.max_compute_cost,
+            ErrorCode::ComputeCostExceeded
+        );
+
+        self.status = ComputationStatus::Completed;
+        self.executor = Some(executor);
+        self.encrypted_outputs = encrypted_outputs;
+        self.compute_cost = compute_cost;
+        self.result_proof = result_proof;
+        self.completed_at = now;
+        Ok(())
+    }
+
+    // A claimed request is kept as evidence until its executor is slashed or the
+    // slashing window has passed
+    pub fn require_refundable(&self, now: i64) -> Result<()> {
+        require!(
+            self.status == ComputationStatus::Queued,
+            ErrorCode::ComputationNotPending
+        );
+        require!(now >= self.timeout_at, ErrorCode::ComputationNotTimedOut);
+        require!(
+            self.assigned_executor.is_none() || self.slashed ||
+            now >= self.timeout_at.saturating_add(SLASHING_WINDOW),
+            ErrorCode::SlashingWindowOpen
+        );
+        Ok(())
+    }
+
+    // Likewise a proven result stays disputable for the slashing window
+    pub fn require_closable(&self, now: i64) -> Result<()> {
+        require!(
+            self.status == ComputationStatus::Completed,
+            ErrorCode::ComputationNotPending
+        );
+        require!(
+            self.result_proof.is_none() || self.slashed ||
+            now >= self.completed_at.saturating_add(SLASHING_WINDOW),
+            ErrorCode::SlashingWindowOpen
+        );
+        Ok(())
+    }
+
+    // Whether the request is evidence against `executor` for `reason`; a request can
+    // only get its executor slashed once. A wrong result still needs its proof to fail.
+    pub fn require_slashable(&self, executor: &Pubkey, reason: SlashReason, now: i64) -> Result<()> {
+        let evidence = match reason {
+            SlashReason::MissedDeadline => {
+                self.status == ComputationStatus::Queued &&
+                now >= self.timeout_at &&
+                self.assigned_executor == Some(*executor)
+            }
+            SlashReason::InvalidResult => {
+                self.status == ComputationStatus::Completed &&
+                self.executor == Some(*executor) &&
+                self.result_proof.is_some()
+            }
+        };
+        require!(evidence && !self.slashed, ErrorCode::NoSlashingEvidence);
+        Ok(())
+    }
+
+    // Public input of the result proof: a commitment to the request's inputs and outputs,
+    // truncated to fit below the BN254 scalar field
+    pub fn result_commitment(&self) -> [u8; groth16::FIELD_ELEMENT_LEN] {
+        let mut preimage = Vec::new();
+        for input in self.inputs.iter() {
+            preimage.extend_from_slice(input.as_ref());
+        }
+        for output in self.encrypted_outputs.iter() {
+            preimage.extend_from_slice(&(output.len() as u32).to_le_bytes());
+            preimage.extend_from_slice(output);
+        }
+
+        let mut commitment = solana_sha256_hasher::hash(&preimage).to_bytes();
+        commitment[0] &= 0x1f;
+        commitment
+    }
+}
+
+pub const MAX_CALLBACK_ACCOUNTS: usize = 8;
+// Highest compute reservation a callback may ask for, leaving room for the submission itself
+pub const MAX_CALLBACK_COMPUTE_UNITS: u32 = 1_000_000;
+
+// Instruction invoked on a consumer program once a computation completes. Its data is
+// `discriminator` followed by a borsh-encoded ComputationCallbackData.
+//
+// The first account is the computation request, read-only and signing as a PDA of this
+// program; the registered accounts follow. Anyone can call the consumer with a forged
+// payload, so its handler must check that the first account is a signer, owned by this
+// program and equal to `ComputationCallbackData::request` before trusting the outputs.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ComputationCallback {
+    pub program_id: Pubkey,         // Consumer program to invoke
+    pub discriminator: [u8; 8],     // Instruction discriminator of the consumer's handler
+    pub accounts: Vec<CallbackAccount>, // Accounts the consumer's handler expects, in order
+    pub compute_units: u32,         // Units that must remain when the callback is invoked
+}
+
+impl ComputationCallback {
+    // Option tag + program_id + discriminator + accounts + compute_units
+    pub const MAX_LEN: usize = 1 + 32 + 8 + 4 + MAX_CALLBACK_ACCOUNTS * CallbackAccount::LEN + 4;
+
+    pub fn validate(&self) -> Result<()> {
+        // Calling back into this program could re-enter result submission
+        require!(self.program_id != crate::ID, ErrorCode::InvalidCallback);
+        require!(
+            self.accounts.len() <= MAX_CALLBACK_ACCOUNTS,
+            ErrorCode::InvalidCallback
+        );
+        require!(
+            self.compute_units > 0 && self.compute_units <= MAX_CALLBACK_COMPUTE_UNITS,
+            ErrorCode::InvalidCallback
+        );
+        Ok(())
+    }
+
+    // Build the callback instruction. Only the request signs; the registered accounts are
+    // never forwarded as signers, so the consumer gains no authority from the executor's
+    // transaction.
+    pub fn instruction(&self, payload: &ComputationCallbackData) -> Result<Instruction> {
+        let mut data = self.discriminator.to_vec();
+        payload.serialize(&mut data)?;
+
+        let accounts = std::iter::once(AccountMeta::new_readonly(payload.request, true))
+            .chain(self.accounts.iter().map(|account| match account.is_writable {
+                true => AccountMeta::new(account.pubkey, false),
+                false => AccountMeta::new_readonly(account.pubkey, false),
+            }))
+            .collect();
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        })
+    }
+
+    // The passed accounts must be exactly the registered ones followed by the program
+    pub fn check_accounts(&self, account_infos: &[AccountInfo]) -> Result<()> {
+        require!(
+            account_infos.len() == self.accounts.len() + 1,
+            ErrorCode::InvalidCallback
+        );
+
+        for (expected, account_info) in self.accounts.iter().zip(account_infos.iter()) {
+            require!(
+                *account_info.key == expected.pubkey &&
+                (!expected.is_writable || account_info.is_writable),
+                ErrorCode::InvalidCallback
+            );
+        }
+
+        let program = &account_infos[self.accounts.len()];
+        require!(
+            *program.key == self.program_id && program.executable,
+            ErrorCode::InvalidCallback
+        );
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CallbackAccount {
+    pub pubkey: Pubkey,
+    pub is_writable: bool,
+}
+
+impl CallbackAccount {
+    pub const LEN: usize = 32 + 1;
+}
+
+// Payload handed to a computation callback
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ComputationCallbackData {
+    pub request: Pubkey,
+    pub encrypted_compute: Pubkey,
+    pub definition_id: String,
+    pub encrypted_outputs: Vec<Vec<u8>>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComputationStatus {
+    Queued,                         // Waiting for an executor
+    Completed,                      // Output written and fee paid out
+}
+
+// Protocol cut is capped so fee terms can't be made confiscatory (10%)
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 1_000;
+pub const MAX_FEE_MINTS: usize = 8;
+
+#[account]
+pub struct FeeConfig {
+    pub authority: Pubkey,           // Who may change fee terms and the mint whitelist
+    pub treasury: Pubkey,           // Wallet receiving the protocol's cut
+    pub protocol_fee_bps: u16,      // Protocol cut of every fee, in basis points
+    pub allowed_mints: Vec<Pubkey>, // SPL mints fees may be paid in, besides SOL
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl FeeConfig {
+    // discriminator + authority + treasury + protocol_fee_bps + allowed_mints + bump
+    pub const LEN: usize = 8 + 32 + 32 + 2 + 4 + MAX_FEE_MINTS * 32 + 1;
+
+    // SOL fees need no extra accounts; SPL fees must be in a whitelisted mint and come
+    // with the request's vault
+    pub fn require_fee_accounts(&self, fee_mint: Option<&Pubkey>, has_fee_vault: bool) -> Result<()> {
+        if let Some(mint) = fee_mint {
+            require!(self.allowed_mints.contains(mint), ErrorCode::FeeMintNotAllowed);
+            require!(has_fee_vault, ErrorCode::InvalidFeeAccounts);
+        }
+        Ok(())
+    }
+}
+
+// Holds one request's fee. SOL is kept as lamports on this account; SPL tokens sit in
+// a vault token account whose authority is this escrow.
+#[account]
+pub struct FeeEscrow {
+    pub request: Pubkey,             // Computation request the fee is for
+    pub mint: Option<Pubkey>,        // SPL mint of the fee (None = SOL)
+    pub amount: u64,                // Fee still held (0 once paid out)
+    pub protocol_fee_bps: u16,      // Protocol cut at the time the request was queued
+    pub treasury: Pubkey,           // Treasury at the time the request was queued
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl FeeEscrow {
+    // discriminator + request + mint + amount + protocol_fee_bps + treasury + bump
+    pub const LEN: usize = 8 + 32 + 1 + 32 + 8 + 2 + 32 + 1;
+
+    // Empty the escrow, returning the executor's share and the protocol's cut
+    pub fn release(&mut self) -> (u64, u64) {
+        let protocol_share = (self.amount as u128 * self.protocol_fee_bps as u128 / 10_000) as u64;
+        let executor_share = self.amount - protocol_share;
+        self.amount = 0;
+        (executor_share, protocol_share)
+    }
+}
+
+pub const EXECUTOR_INITIAL_REPUTATION: u32 = 100;
+// Long enough to cover the full timeout window of any request a node may have worked on,
+// plus the slashing window after it
+pub const EXECUTOR_UNSTAKE_COOLDOWN: i64 = MAX_COMPUTATION_TIMEOUT + SLASHING_WINDOW;
+
+#[account]
+pub struct ExecutorRegistry {
+    pub authority: Pubkey,           // Who manages the stake vault
+    pub stake_mint: Option<Pubkey>,  // SPL mint executors stake (None = SOL)
+    pub min_stake: u64,             // Stake a node needs before it may submit results
+    pub treasury: Pubkey,           // Receives slashed stake (into a token account it owns for SPL stake)
+    pub created_at: i64,            // Timestamp when the registry was created
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl ExecutorRegistry {
+    // discriminator + authority + stake_mint + min_stake + treasury + created_at + bump
+    pub const LEN: usize = 8 + 32 + 1 + 32 + 8 + 32 + 8 + 1;
+}
+
+#[account]
+pub struct ExecutorNode {
+    pub authority: Pubkey,           // Key the node signs results with
+    pub stake: u64,                 // Stake currently at risk, in lamports or stake mint units
+    pub reputation: u32,            // Rises with each result, drops when slashed
+    pub active: bool,               // Whether the node accepts work
+    pub results_submitted: u32,     // Number of results submitted
+    pub times_slashed: u32,         // Number of times the node was slashed
+    pub registered_at: i64,         // Timestamp when the node registered
+    pub deregistered_at: i64,       // Timestamp when the node deregistered (0 if active)
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl ExecutorNode {
+    // discriminator + authority + stake + reputation + active + counters + timestamps + bump
+    pub const LEN: usize = 8 + 32 + 8 + 4 + 1 + 4 + 4 + 8 + 8 + 1;
+
+    pub fn require_eligible(&self, min_stake: u64) -> Result<()> {
+        require!(
+            self.active && self.stake >= min_stake,
+            ErrorCode::ExecutorNotEligible
+        );
+        Ok(())
+    }
+
+    pub fn record_result(&mut self) {
+        self.results_submitted = self.results_submitted.saturating_add(1);
+        self.reputation = self.reputation.saturating_add(1);
+    }
+
+    // Take the reason's share of the stake, returning how much was slashed
+    pub fn slash(&mut self, reason: SlashReason) -> u64 {
+        let slashed = (self.stake as u128 * reason.stake_penalty_bps() as u128 / 10_000) as u64;
+        self.stake -= slashed;
+        self.reputation = self.reputation.saturating_sub(reason.reputation_penalty());
+        self.times_slashed = self.times_slashed.saturating_add(1);
+        slashed
+    }
+
+    pub fn require_withdrawable(&self, now: i64) -> Result<()> {
+        require!(
+            !self.active && now >= self.deregistered_at.saturating_add(EXECUTOR_UNSTAKE_COOLDOWN),
+            ErrorCode::ExecutorStakeLocked
+        );
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlashReason {
+    InvalidResult,                  // A submitted result was shown to be wrong
+    MissedDeadline,                 // The node failed to deliver work it took on in time
+}
+
+impl SlashReason {
+    // Share of the node's stake taken, in basis points
+    pub fn stake_penalty_bps(&self) -> u16 {
+        match self {
+            SlashReason::InvalidResult => 5_000,
+            SlashReason::MissedDeadline => 1_000,
+        }
+    }
+
+    pub fn reputation_penalty(&self) -> u32 {
+        match self {
+            SlashReason::InvalidResult => 50,
+            SlashReason::MissedDeadline => 10,
+        }
+    }
+}
+
+#[account]
+pub struct DefinitionRegistry {
+    pub authority: Pubkey,           // Who may register, rotate and deprecate definitions
+    pub created_at: i64,            // Timestamp when the registry was created
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl DefinitionRegistry {
+    // discriminator + authority + created_at + bump
+    pub const LEN: usize = 8 + 32 + 8 + 1;
+}
+
+#[account]
+pub struct ComputationDefinition {
+    pub definition_id: String,      // Computation name, e.g. "private_sum"
+    pub version: u32,               // Definition version, starting at 1 and bumped on rotation
+    pub spec: ComputationSpec,      // What the computation accepts and produces
+    pub superseded: bool,           // Whether a newer version has been published
+    pub deprecated: bool,           // Whether new requests are still accepted
+    pub registered_at: i64,         // Timestamp when the definition was registered
+    pub deprecated_at: i64,         // Timestamp when the definition was deprecated (0 if active)
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl ComputationDefinition {
+    // discriminator + definition_id + version + spec + flags + timestamps + bump
+    pub const LEN: usize = 8
+        + 4 + MAX_DEFINITION_ID_LEN
+        + 4
+        + ComputationSpec::MAX_LEN
+        + 1 + 1
+        + 8 + 8
+        + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ComputationSpec {
+    pub circuit_hash: [u8; 32],     // Hash of the compiled circuit executors must run
+    pub input_arity: u8,            // Number of input ciphertexts, the queuing account included
+    pub output_arity: u8,           // Number of output ciphertexts
+    pub scheme: CiphertextScheme,   // Encryption scheme of inputs and outputs
+    pub max_compute_cost: u64,      // Highest cost an executor may report
+    pub allowed_executors: Vec<Pubkey>, // Executors allowed to submit results (empty = any registered)
+    pub result_circuit: Option<ResultCircuit>, // Circuit results are proven against (None = unproven)
+}
+
+impl ComputationSpec {
+    // circuit_hash + arities + scheme + max_compute_cost + allowed_executors + result_circuit
+    pub const MAX_LEN: usize = 32 + 1 + 1 + 1 + 8 + 4 + MAX_ALLOWED_EXECUTORS * 32 + 1 + ResultCircuit::LEN;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.input_arity >= 1 && self.input_arity as usize <= MAX_COMPUTATION_INPUTS,
+            ErrorCode::InvalidComputationDefinition
+        );
+        require!(
+            self.output_arity >= 1 && self.output_arity as usize <= MAX_COMPUTATION_OUTPUTS,
+            ErrorCode::InvalidComputationDefinition
+        );
+        require!(
+            self.circuit_hash != [0u8; 32] && self.max_compute_cost > 0,
+            ErrorCode::InvalidComputationDefinition
+        );
+        require!(
+            self.allowed_executors.len() <= MAX_ALLOWED_EXECUTORS,
+            ErrorCode::InvalidComputationDefinition
+        );
+        if let Some(circuit) = self.result_circuit.as_ref() {
+            require!(
+                !circuit.circuit_id.is_empty() && circuit.circuit_id.len() <= MAX_CIRCUIT_ID_LEN,
+                ErrorCode::InvalidComputationDefinition
+            );
+        }
+        Ok(())
+    }
+
+    pub fn allows_executor(&self, executor: &Pubkey) -> bool {
+        self.allowed_executors.is_empty() || self.allowed_executors.contains(executor)
+    }
+
+    // Executors can only run the circuit over ciphertexts under the definition's scheme
+    pub fn require_input(&self, input: &EncryptedCompute) -> Result<()> {
+        require!(input.has_ciphertext(), ErrorCode::NoEncryptedData);
+        require!(
+            input.ciphertext_scheme() == Some(self.scheme),
+            ErrorCode::CiphertextSchemeMismatch
+        );
+        Ok(())
+    }
+
+    // Disputed results are checked against the result circuit's key, which takes the
+    // request's result commitment as its only public input
+    pub fn require_result_key(&self, verifying_key: &VerifyingKey) -> Result<()> {
+        let Some(circuit) = self.result_circuit.as_ref() else {
+            return err!(ErrorCode::InvalidVerifyingKey);
+        };
+        require!(
+            verifying_key.circuit_id == circuit.circuit_id &&
+            verifying_key.version == circuit.key_version &&
+            verifying_key.key.num_public_inputs() == 1,
+            ErrorCode::InvalidVerifyingKey
+        );
+        Ok(())
+    }
+}
+
+// A registered verifying key that executors prove their results against
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ResultCircuit {
+    pub circuit_id: String,         // Circuit id in the verifier registry
+    pub key_version: u32,           // Verifying key version to check proofs against
+}
+
+impl ResultCircuit {
+    // circuit_id + key_version
+    pub const LEN: usize = 4 + MAX_CIRCUIT_ID_LEN + 4;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum CiphertextScheme {
+    Rescue,                         // Rescue cipher over the MPC field, as used by Arcium
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+// Maximum length of a circuit id; it is used as a PDA seed so must fit in 32 bytes
+pub const MAX_CIRCUIT_ID_LEN: usize = 32;
+
+#[account]
+pub struct VerifierRegistry {
+    pub authority: Pubkey,           // Who may register, rotate and deprecate keys
+    pub created_at: i64,            // Timestamp when the registry was created
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl VerifierRegistry {
+    // discriminator + authority + created_at + bump
+    pub const LEN: usize = 8 + 32 + 8 + 1;
+
+    pub fn require_authority(&self, signer: &Pubkey) -> Result<()> {
+        require!(*signer == self.authority, ErrorCode::Unauthorized);
+        Ok(())
+    }
+}
+
+#[account]
+pub struct VerifyingKey {
+    pub circuit_id: String,         // Circuit name, e.g. "range_proof"
+    pub version: u32,               // Key version, starting at 1 and bumped on rotation
+    pub key: Groth16VerifyingKey,   // The Groth16 verifying key itself
+    pub superseded: bool,           // Whether a newer version has been published
+    pub deprecated: bool,           // Whether proofs against this key are still accepted
+    pub registered_at: i64,         // Timestamp when the key was registered
+    pub deprecated_at: i64,         // Timestamp when the key was deprecated (0 if active)
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl VerifyingKey {
+    // discriminator + circuit_id + version + alpha/beta/gamma/delta + ic + flags + timestamps + bump
+    pub const LEN: usize = 8
+        + 4 + MAX_CIRCUIT_ID_LEN
+        + 4
+        + groth16::G1_POINT_LEN + 3 * groth16::G2_POINT_LEN
+        + 4 + (groth16::MAX_PUBLIC_INPUTS + 1) * groth16::G1_POINT_LEN
+        + 1 + 1
+        + 8 + 8
+        + 1;
+
+    // Version 1 of a newly registered circuit
+    pub fn register(circuit_id: String, key: Groth16VerifyingKey, now: i64, bump: u8) -> Result<Self> {
+        require!(!circuit_id.is_empty(), ErrorCode::InvalidData);
+        require!(circuit_id.len() <= MAX_CIRCUIT_ID_LEN, ErrorCode::DataTooLong);
+        key.validate()?;
+
+        Ok(VerifyingKey {
+            circuit_id,
+            version: 1,
+            key,
+            superseded: false,
+            deprecated: false,
+            registered_at: now,
+            deprecated_at: 0,
+            bump,
+        })
+    }
+
+    // Supersede this version with the next one; rotation always continues from the latest
+    pub fn rotate(&mut self, key: Groth16VerifyingKey, now: i64, bump: u8) -> Result<Self> {
+        require!(!self.superseded, ErrorCode::VerifyingKeySuperseded);
+        key.validate()?;
+
+        self.superseded = true;
+        Ok(VerifyingKey {
+            circuit_id: self.circuit_id.clone(),
+            version: self.version + 1,
+            key,
+            superseded: false,
+            deprecated: false,
+            registered_at: now,
+            deprecated_at: 0,
+            bump,
+        })
+    }
+
+    pub fn deprecate(&mut self, now: i64) -> Result<()> {
+        require!(!self.deprecated, ErrorCode::VerifyingKeyDeprecated);
+        self.deprecated = true;
+        self.deprecated_at = now;
+        Ok(())
+    }
+}
+
+// Proof ids and types are bounded so records have a fixed maximum size
+pub const MAX_PROOF_ID_LEN: usize = 32;
+pub const MAX_PROOF_TYPE_LEN: usize = 32;
+
+#[account]
+pub struct ZkProofRecord {
+    pub encrypted_compute: Pubkey,   // The account this proof belongs to
+    pub proof_id: String,           // Caller-chosen identifier, unique per account
+    pub circuit_id: String,         // Circuit the proof was generated for
+    pub key_version: u32,           // Verifying key version the proof targets
+    pub proof_type: String,         // Free-form proof type label
+    pub proof_data: Vec<u8>,        // Raw Groth16 proof bytes (A || B || C)
+    pub public_inputs: Vec<u8>,     // Concatenated 32-byte public inputs
+    pub submitter: Pubkey,          // Who stored the proof
+    pub slot: u64,                  // Slot in which the proof was stored
+    pub verified: bool,             // Whether the proof passed on-chain verification
+    pub verified_slot: u64,         // Slot in which it was verified (0 if never)
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl ZkProofRecord {
+    // discriminator + encrypted_compute + strings + key_version + proof + inputs + submitter + slots + flags + bump
+    pub const LEN: usize = 8
+        + 32
+        + 4 + MAX_PROOF_ID_LEN
+        + 4 + MAX_CIRCUIT_ID_LEN
+        + 4
+        + 4 + MAX_PROOF_TYPE_LEN
+        + 4 + groth16::PROOF_LEN
+        + 4 + groth16::MAX_PUBLIC_INPUTS * groth16::FIELD_ELEMENT_LEN
+        + 32
+        + 8
+        + 1
+        + 8
+        + 1;
+
+    // Stored for exactly this version of a still-accepted key, with a proof and public
+    // inputs of the sizes it expects
+    pub fn require_matches(&self, verifying_key: &VerifyingKey) -> Result<()> {
+        require!(
+            verifying_key.circuit_id == self.circuit_id && verifying_key.version == self.key_version,
+            ErrorCode::InvalidVerifyingKey
+        );
+        require!(!verifying_key.deprecated, ErrorCode::VerifyingKeyDeprecated);
+        require!(
+            self.proof_data.len() == groth16::PROOF_LEN &&
+            self.public_inputs.len() == verifying_key.key.num_public_inputs() * groth16::FIELD_ELEMENT_LEN,
+            ErrorCode::InvalidZKProof
+        );
+        Ok(())
+    }
+}
+
+// Audit entries are bounded so every page has a fixed maximum size
+pub const AUDIT_ENTRIES_PER_PAGE: usize = 8;
+pub const MAX_AUDIT_EVENT_TYPE_LEN: usize = 32;
+pub const MAX_AUDIT_EVENT_DATA_LEN: usize = 256;
+pub const MAX_AUDIT_USER_ADDRESS_LEN: usize = 64;
+pub const MAX_AUDIT_TRAIL_NAME_LEN: usize = 32;
+pub const MAX_AUDIT_TRAIL_MEMBERS: usize = 16;
+
+// The trail is owned by whoever is the audited account's authority at the time, so it
+// follows authority transfers
+#[account]
+pub struct AuditTrail {
+    pub encrypted_compute: Pubkey,   // The account this trail audits
+    pub name: String,               // Trail name, unique per account
+    pub access_level: AuditAccessLevel, // Sensitivity of the trail's entries
+    pub members: Vec<AuditTrailMember>, // Authorized readers and auditors
+    pub created_at: i64,            // Timestamp when the trail was created
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl AuditTrail {
+    // discriminator + encrypted_compute + name + access_level + members + created_at + bump
+    pub const LEN: usize = 8 + 32
+        + 4 + MAX_AUDIT_TRAIL_NAME_LEN
+        + 1
+        + 4 + MAX_AUDIT_TRAIL_MEMBERS * AuditTrailMember::LEN
+        + 8
+        + 1;
+
+    fn role_of(&self, key: &Pubkey) -> Option<AuditTrailRole> {
+        self.members.iter().find(|m| m.member == *key).map(|m| m.role.clone())
+    }
+
+    // The owner and auditors may append at every access level
+    pub fn can_append(&self, key: &Pubkey, owner: &Pubkey) -> bool {
+        key == owner || self.role_of(key) == Some(AuditTrailRole::Auditor)
+    }
+
+    // Public trails are open to anyone, restricted trails to any member,
+    // confidential trails to auditors only
+    pub fn can_read(&self, key: &Pubkey, owner: &Pubkey) -> bool {
+        if key == owner {
+            return true;
+        }
+        match self.access_level {
+            AuditAccessLevel::Public => true,
+            AuditAccessLevel::Restricted => self.role_of(key).is_some(),
+            AuditAccessLevel::Confidential => self.role_of(key) == Some(AuditTrailRole::Auditor),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct AuditTrailMember {
+    pub member: Pubkey,
+    pub role: AuditTrailRole,
+}
+
+impl AuditTrailMember {
+    pub const LEN: usize = 32 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum AuditTrailRole {
+    Reader,
+    Auditor,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum AuditAccessLevel {
+    Public,
+    Restricted,
+    Confidential,
+}
+
+impl AuditAccessLevel {
+    pub fn from_u8(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(AuditAccessLevel::Public),
+            1 => Ok(AuditAccessLevel::Restricted),
+            2 => Ok(AuditAccessLevel::Confidential),
+            _ => err!(ErrorCode::InvalidAccessLevel),
+        }
+    }
+}
+
+#[account]
+pub struct AuditLog {
+    pub audit_trail: Pubkey,        // The trail whose events are logged
+    pub entry_count: u64,           // Total number of entries ever appended
+    pub page_count: u32,            // Number of pages opened so far
+    pub head_hash: [u8; 32],        // Hash of the latest entry (zero when empty)
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl AuditLog {
+    // discriminator + audit_trail + entry_count + page_count + head_hash + bump
+    pub const LEN: usize = 8 + 32 + 8 + 4 + 32 + 1;
+
+    // Page the next entry will be written to
+    pub fn current_page(&self) -> u32 {
+        (self.entry_count / AUDIT_ENTRIES_PER_PAGE as u64) as u32
+    }
+}
+
+#[account]
+pub struct AuditLogPage {
+    pub audit_log: Pubkey,          // The log this page belongs to
+    pub page_index: u32,            // Position of this page in the log
+    pub entries: Vec<AuditEntry>,   // Up to AUDIT_ENTRIES_PER_PAGE entries, append-only
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl AuditLogPage {
+    // discriminator + audit_log + page_index + entries + bump
+    pub const LEN: usize = 8 + 32 + 4 + 4 + AUDIT_ENTRIES_PER_PAGE * AuditEntry::LEN + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub index: u64,                 // Sequence number across the whole log
+    pub timestamp: i64,             // When the event was logged
+    pub slot: u64,                  // Slot in which the event was logged
+    pub actor: Pubkey,              // Signer that logged the event
+    pub event_type: String,         // Event category
+    pub event_data: String,         // Encrypted event payload
+    pub user_address: String,       // Subject of the event
+    pub prev_hash: [u8; 32],        // Hash of the previous entry
+    pub entry_hash: [u8; 32],       // Hash over this entry, including prev_hash
+}
+
+impl AuditEntry {
+    pub const LEN: usize = 8 + 8 + 8 + 32
+        + 4 + MAX_AUDIT_EVENT_TYPE_LEN
+        + 4 + MAX_AUDIT_EVENT_DATA_LEN
+        + 4 + MAX_AUDIT_USER_ADDRESS_LEN
+        + 32 + 32;
+
+    // sha256 over every field except entry_hash; strings are length-prefixed
+    pub fn compute_hash(&self) -> [u8; 32] {
+        solana_sha256_hasher::hashv(&[
+            &self.prev_hash,
+            &self.index.to_le_bytes(),
+            &self.timestamp.to_le_bytes(),
+            &self.slot.to_le_bytes(),
+            self.actor.as_ref(),
+            &(self.event_type.len() as u32).to_le_bytes(),
+            self.event_type.as_bytes(),
+            &(self.event_data.len() as u32).to_le_bytes(),
+            self.event_data.as_bytes(),
+            &(self.user_address.len() as u32).to_le_bytes(),
+            self.user_address.as_bytes(),
+        ])
+        .to_bytes()
+    }
+}
+
+// Operations a time-lock can guard, as bits of EncryptedCompute::lock_guards
+pub const LOCK_GUARD_UPDATE: u16 = 1 << 0;
+pub const LOCK_GUARD_PROCESS: u16 = 1 << 1;
+pub const LOCK_GUARD_CLOSE: u16 = 1 << 2;
+pub const LOCK_GUARD_PROVE: u16 = 1 << 3;
+pub const LOCK_GUARD_AUDIT: u16 = 1 << 4;
+pub const LOCK_GUARD_DISCLOSURE: u16 = 1 << 5;
+pub const LOCK_GUARD_CREDENTIAL: u16 = 1 << 6;
+pub const LOCK_GUARD_ALL: u16 = (1 << 7) - 1;
+// By default a lock freezes the data itself; proofs, audits and credentials stay available
+pub const LOCK_GUARD_DEFAULT: u16 = LOCK_GUARD_UPDATE | LOCK_GUARD_PROCESS | LOCK_GUARD_CLOSE;
+
+impl EncryptedCompute {
+    // Move to a new lifecycle status, rejecting moves the transition table does not allow
+    pub fn transition_to(&mut self, next: EncryptedComputeStatus) -> Result<()> {
+        require!(
+            self.status.can_transition_to(&next),
+            ErrorCode::InvalidStateTransition
+        );
+        self.status = next;
+        Ok(())
+    }
+
+    // Side effects (proofs, audits, credentials) may be recorded until the account closes
+    pub fn require_active(&self) -> Result<()> {
+        require!(
+            self.status != EncryptedComputeStatus::Closed,
+            ErrorCode::InvalidStateTransition
+        );
+        Ok(())
+    }
+
+    // Whether a time-lock is set and its expiration has not yet passed
+    pub fn time_lock_active(&self, now: i64) -> bool {
+        self.is_locked && now < self.time_lock_expiration
+    }
+
+    // Lock the data until `expiration`; locks don't stack and closed accounts can't be locked
+    pub fn apply_time_lock(&mut self, expiration: i64, now: i64) -> Result<()> {
+        require!(expiration > now, ErrorCode::InvalidTimeLock);
+        require!(!self.time_lock_active(now), ErrorCode::TimeLockActive);
+        self.require_active()?;
+
+        self.time_lock_expiration = expiration;
+        self.is_locked = true;
+        self.unlock_conditions_met = false;
+        self.time_lock_committed = false;
+        self.time_locks_applied = self.time_locks_applied.saturating_add(1);
+        // Every lock starts with a fresh set of unlock conditions
+        if let Some(policy) = self.unlock_policy.as_mut() {
+            policy.reset();
+        }
+        Ok(())
+    }
+
+    // Drop an active, uncommitted lock and return to the unlocked default state
+    pub fn revoke_time_lock(&mut self, now: i64) -> Result<()> {
+        require!(
+            self.time_lock_active(now) && !self.unlock_conditions_met,
+            ErrorCode::TimeLockNotActive
+        );
+        require!(!self.time_lock_committed, ErrorCode::TimeLockCommitted);
+
+        self.clear_time_lock();
+        Ok(())
+    }
+
+    pub fn commit_time_lock(&mut self, now: i64) -> Result<()> {
+        require!(self.time_lock_active(now), ErrorCode::TimeLockNotActive);
+
+        self.time_lock_committed = true;
+        Ok(())
+    }
+
+    pub fn extend_time_lock(&mut self, new_expiration: i64, now: i64) -> Result<()> {
+        require!(self.time_lock_active(now), ErrorCode::TimeLockNotActive);
+        require!(
+            new_expiration > self.time_lock_expiration,
+            ErrorCode::InvalidTimeLock
+        );
+
+        self.time_lock_expiration = new_expiration;
+        Ok(())
+    }
+
+    // Committed locks can't be shortened, and a shortened lock must still end in the future
+    pub fn shorten_time_lock(&mut self, new_expiration: i64, now: i64) -> Result<()> {
+        require!(self.time_lock_active(now), ErrorCode::TimeLockNotActive);
+        require!(!self.time_lock_committed, ErrorCode::TimeLockCommitted);
+        require!(
+            new_expiration > now && new_expiration < self.time_lock_expiration,
+            ErrorCode::InvalidTimeLock
+        );
+
+        self.time_lock_expiration = new_expiration;
+        Ok(())
+    }
+
+    // The unlock policy can't change while a lock holds
+    pub fn set_unlock_policy(&mut self, unlock_policy: Option<UnlockPolicy>, now: i64) -> Result<()> {
+        require!(!self.time_lock_active(now), ErrorCode::TimeLockActive);
+
+        let mut unlock_policy = unlock_policy;
+        if let Some(policy) = unlock_policy.as_mut() {
+            policy.validate()?;
+            policy.reset();
+        }
+        self.unlock_policy = unlock_policy;
+        Ok(())
+    }
+
+    // The guards are fixed for the duration of a lock, otherwise it could be lifted mid-lock
+    pub fn set_time_lock_guards(&mut self, lock_guards: u16, now: i64) -> Result<()> {
+        require!(lock_guards & !LOCK_GUARD_ALL == 0, ErrorCode::InvalidData);
+        require!(!self.time_lock_active(now), ErrorCode::TimeLockActive);
+
+        self.lock_guards = lock_guards;
+        Ok(())
+    }
+
+    // Release a lock whose expiration has passed; returns whether anything changed
+    pub fn release_expired_time_lock(&mut self, now: i64) -> bool {
+        if self.is_locked && !self.unlock_conditions_met && now >= self.time_lock_expiration {
+            self.is_locked = false;
+            self.unlock_conditions_met = true;
+            self.time_lock_committed = false;
+            return true;
+        }
+        false
+    }
+
+    // Record a satisfied unlock condition; releases the lock once the policy holds.
+    // Returns whether the lock was released.
+    pub fn record_unlock_condition(&mut self, index: usize, evidence: &UnlockEvidence) -> Result<bool> {
+        require!(self.time_lock_active(evidence.unix_timestamp), ErrorCode::TimeLockNotActive);
+
+        let policy = self.unlock_policy.as_mut().ok_or(error!(ErrorCode::NoUnlockPolicy))?;
+        policy.record(index, evidence)?;
+
+        if policy.is_satisfied() {
+            self.is_locked = false;
+            self.unlock_conditions_met = true;
+            self.time_lock_committed = false;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn clear_time_lock(&mut self) {
+        self.time_lock_expiration = 0;
+        self.is_locked = false;
+        self.unlock_conditions_met = false;
+        self.time_lock_committed = false;
+    }
+
+    // The authority holds every permission; delegates hold what they were granted until expiry
+    pub fn has_permission(&self, signer: &Pubkey, permission: u8, now: i64) -> bool {
+        if *signer == self.authority {
+            return true;
+        }
+        self.delegates.iter().any(|d| {
+            d.delegate == *signer &&
+            d.permissions & permission == permission &&
+            (d.expires_at == 0 || now < d.expires_at)
+        })
+    }
+
+    pub fn require_permission(&self, signer: &Pubkey, permission: u8) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(self.has_permission(signer, permission, now), ErrorCode::Unauthorized);
+        Ok(())
+    }
+
+    pub fn grant_permission(&mut self,
+                            delegate: Pubkey,
+                            permissions: u8,
+                            expires_at: Option<i64>,
+                            now: i64) -> Result<()> {
+        require!(
+            permissions != 0 && permissions & !PERMISSION_ALL == 0,
+            ErrorCode::InvalidData
+        );
+        require!(delegate != self.authority, ErrorCode::InvalidData);
+        let expires_at = expires_at.unwrap_or(0);
+        require!(expires_at == 0 || expires_at > now, ErrorCode::InvalidData);
+
+        // Drop expired grants so they don't hold slots
+        self.delegates.retain(|d| d.expires_at == 0 || now < d.expires_at);
+
+        if let Some(existing) = self.delegates.iter_mut().find(|d| d.delegate == delegate) {
+            existing.permissions = permissions;
+            existing.expires_at = expires_at;
+            return Ok(());
+        }
+
+        require!(self.delegates.len() < MAX_DELEGATES, ErrorCode::TooManyDelegates);
+        self.delegates.push(Delegate { delegate, permissions, expires_at });
+        Ok(())
+    }
+
+    pub fn revoke_permission(&mut self, delegate: &Pubkey) -> Result<()> {
+        let before = self.delegates.len();
+        self.delegates.retain(|d| d.delegate != *delegate);
+        require!(self.delegates.len() < before, ErrorCode::InvalidData);
+        Ok(())
+    }
+
+    // Ownership can't change hands while a time-lock holds
+    pub fn propose_authority_transfer(&mut self, new_authority: Pubkey, now: i64) -> Result<()> {
+        require!(!self.time_lock_active(now), ErrorCode::TimeLockActive);
+        require!(new_authority != self.authority, ErrorCode::InvalidData);
+
+        self.pending_authority = Some(new_authority);
+        Ok(())
+    }
+
+    // Delegates were granted by the previous owner, so they don't carry over
+    pub fn accept_authority_transfer(&mut self, signer: &Pubkey, now: i64) -> Result<()> {
+        let pending = self.pending_authority.ok_or(error!(ErrorCode::NoPendingAuthorityTransfer))?;
+        require!(*signer == pending, ErrorCode::Unauthorized);
+        require!(!self.time_lock_active(now), ErrorCode::TimeLockActive);
+
+        self.authority = pending;
+        self.pending_authority = None;
+        self.delegates.clear();
+        Ok(())
+    }
+
+    // Reject an operation this account guards while its time-lock holds
+    pub fn require_not_time_locked(&self, guard: u16) -> Result<()> {
+        if self.lock_guards & guard != 0 {
+            let now = Clock::get()?.unix_timestamp;
+            self.require_not_time_locked_at(guard, now)?;
+        }
+        Ok(())
+    }
+
+    pub fn require_not_time_locked_at(&self, guard: u16, now: i64) -> Result<()> {
+        if self.lock_guards & guard != 0 {
+            require!(!self.time_lock_active(now), ErrorCode::TimeLockActive);
+        }
+        Ok(())
+    }
+
+    pub fn mark_closed(&mut self, now: i64) -> Result<()> {
+        self.require_not_time_locked_at(LOCK_GUARD_CLOSE, now)?;
+
+        self.transition_to(EncryptedComputeStatus::Closed)
+    }
+
+    // Replace the ciphertext, moving the account to Updated
+    pub fn set_encrypted_data(&mut self, encrypted_data: Vec<u8>, metadata: CiphertextMetadata, now: i64) -> Result<()> {
+        // Validate encrypted data is not empty
+        require!(!encrypted_data.is_empty(), ErrorCode::InvalidData);
+        // Limit the length to prevent excessive storage costs
+        require!(encrypted_data.len() <= MAX_ENCRYPTED_DATA_LEN, ErrorCode::DataTooLong);
+        metadata.validate()?;
+
+        // Respect the account's time-lock policy
+        self.require_not_time_locked_at(LOCK_GUARD_UPDATE, now)?;
+
+        self.encrypted_data = encrypted_data;
+        self.ciphertext_metadata = Some(metadata);
+        self.storage = CiphertextStorage::Inline;
+        self.transition_to(EncryptedComputeStatus::Updated)
+    }
+
+    // Replace the ciphertext with a pointer to off-chain content, moving the account to Updated
+    pub fn set_external_ciphertext(&mut self, pointer: ExternalCiphertext, now: i64) -> Result<()> {
+        pointer.validate()?;
+
+        // Respect the account's time-lock policy
+        self.require_not_time_locked_at(LOCK_GUARD_UPDATE, now)?;
+
+        self.encrypted_data = Vec::new();
+        self.ciphertext_metadata = None;
+        self.storage = CiphertextStorage::External(pointer);
+        self.transition_to(EncryptedComputeStatus::Updated)
+    }
+
+    // Seed the account's address is derived from, after the creator
+    pub fn address_seed(&self) -> &[u8] {
+        if self.legacy_seed.is_empty() {
+            &self.data_hash
+        } else {
+            &self.legacy_seed
+        }
+    }
+
+    pub fn has_ciphertext(&self) -> bool {
+        match self.storage {
+            CiphertextStorage::Inline => !self.encrypted_data.is_empty(),
+            CiphertextStorage::Chunked(_) | CiphertextStorage::External(_) => true,
+        }
+    }
+
+    // Scheme the stored ciphertext is encrypted under; unknown for migrated accounts until
+    // their ciphertext is rewritten
+    pub fn ciphertext_scheme(&self) -> Option<CiphertextScheme> {
+        match &self.storage {
+            CiphertextStorage::External(pointer) => Some(pointer.scheme),
+            CiphertextStorage::Inline | CiphertextStorage::Chunked(_) => {
+                self.ciphertext_metadata.as_ref().map(|metadata| metadata.scheme)
+            }
+        }
+    }
+
+    pub fn begin_ciphertext_upload(&mut self) {
+        self.ciphertext_generation = self.ciphertext_generation.wrapping_add(1);
+        self.ciphertext_upload_open = true;
+        self.pending_chunks = 0;
+    }
+
+    // Chunks are appended strictly in order so finalize can hash them back in sequence
+    pub fn record_ciphertext_chunk(&mut self, index: u32) -> Result<()> {
+        require!(self.ciphertext_upload_open, ErrorCode::NoCiphertextUpload);
+        require!(index == self.pending_chunks, ErrorCode::InvalidData);
+        require!(
+            (index as usize) < MAX_CIPHERTEXT_CHUNKS,
+            ErrorCode::DataTooLong
+        );
+
+        self.pending_chunks += 1;
+        Ok(())
+    }
+
+    // Replace the ciphertext with the staged chunks once they hash to `expected_hash`
+    pub fn finalize_ciphertext(&mut self, chunks: &[&[u8]], expected_hash: [u8; 32],
+                               metadata: CiphertextMetadata, now: i64) -> Result<()> {
+        require!(self.ciphertext_upload_open, ErrorCode::NoCiphertextUpload);
+        metadata.validate()?;
+        require!(
+            self.pending_chunks > 0 && chunks.len() == self.pending_chunks as usize,
+            ErrorCode::CiphertextHashMismatch
+        );
+        require!(
+            solana_sha256_hasher::hashv(chunks).to_bytes() == expected_hash,
+            ErrorCode::CiphertextHashMismatch
+        );
+
+        // Respect the account's time-lock policy
+        self.require_not_time_locked_at(LOCK_GUARD_UPDATE, now)?;
+
+        self.storage = CiphertextStorage::Chunked(ChunkedCiphertext {
+            generation: self.ciphertext_generation,
+            chunk_count: self.pending_chunks,
+            total_len: chunks.iter().map(|chunk| chunk.len() as u64).sum(),
+            sha256: expected_hash,
+        });
+        self.encrypted_data = Vec::new();
+        self.ciphertext_metadata = Some(metadata);
+        self.ciphertext_upload_open = false;
+        self.pending_chunks = 0;
+        self.transition_to(EncryptedComputeStatus::Updated)
+    }
+
+    // Chunks of the stored ciphertext and of an open upload must be kept
+    pub fn chunk_in_use(&self, generation: u32) -> bool {
+        let stored = matches!(&self.storage, CiphertextStorage::Chunked(chunked) if chunked.generation == generation);
+        let staging = self.ciphertext_upload_open && self.ciphertext_generation == generation;
+        stored || staging
+    }
+
+    // Privileged instructions are only callable directly while no multisig governs the account
+    pub fn require_single_authority(&self) -> Result<()> {
+        require!(self.multisig.is_none(), ErrorCode::MultisigRequired);
+        Ok(())
+    }
+
+    pub fn multisig_signer_index(&self, signer: &Pubkey) -> Result<usize> {
+        let multisig = self.multisig.as_ref().ok_or(error!(ErrorCode::MultisigNotEnabled))?;
+        multisig.signer_index(signer).ok_or(error!(ErrorCode::Unauthorized))
+    }
+
+    // Install, replace or remove the multisig; any pending proposal becomes stale
+    pub fn set_multisig(&mut self, multisig: Option<AuthorityMultisig>) -> Result<()> {
+        if let Some(multisig) = multisig.as_ref() {
+            multisig.validate()?;
+        }
+
+        self.multisig = multisig;
+        self.multisig_nonce = self.multisig_nonce.wrapping_add(1);
+        Ok(())
+    }
+
+    // Carry out an approved proposal; closing the account itself is left to the caller
+    pub fn execute_multisig_action(&mut self, proposal: &MultisigProposal, now: i64) -> Result<()> {
+        self.require_approved(proposal)?;
+
+        match proposal.action.clone() {
+            MultisigAction::UpdateEncryptedData { encrypted_data, metadata } => {
+                self.set_encrypted_data(encrypted_data, metadata, now)
+            }
+            MultisigAction::RevokeTimeLock => self.revoke_time_lock(now),
+            MultisigAction::CloseEncryptedCompute => self.mark_closed(now),
+            MultisigAction::SetMultisig { multisig } => self.set_multisig(multisig),
+            MultisigAction::ApplyTimeLock { expiration } => self.apply_time_lock(expiration, now),
+            MultisigAction::CommitTimeLock => self.commit_time_lock(now),
+            MultisigAction::ExtendTimeLock { new_expiration } => self.extend_time_lock(new_expiration, now),
+            MultisigAction::ShortenTimeLock { new_expiration } => self.shorten_time_lock(new_expiration, now),
+            MultisigAction::SetUnlockPolicy { unlock_policy } => self.set_unlock_policy(unlock_policy, now),
+            MultisigAction::SetTimeLockGuards { lock_guards } => self.set_time_lock_guards(lock_guards, now),
+            MultisigAction::GrantPermission { delegate, permissions, expires_at } => {
+                self.grant_permission(delegate, permissions, expires_at, now)
+            }
+            MultisigAction::RevokePermission { delegate } => self.revoke_permission(&delegate),
+            MultisigAction::BeginCiphertextUpload => {
+                self.require_not_time_locked_at(LOCK_GUARD_UPDATE, now)?;
+                self.require_active()?;
+                self.begin_ciphertext_upload();
+                Ok(())
+            }
+            MultisigAction::SetExternalCiphertext { pointer } => self.set_external_ciphertext(pointer, now),
+            MultisigAction::ProposeAuthorityTransfer { new_authority } => {
+                self.propose_authority_transfer(new_authority, now)
+            }
+            // These need accounts of their own; their instructions take the proposal instead
+            MultisigAction::AppendCiphertextChunk { .. } |
+            MultisigAction::FinalizeCiphertext { .. } |
+            MultisigAction::QueueComputation { .. } |
+            MultisigAction::InitializeRevocationList |
+            MultisigAction::IssueCredential { .. } |
+            MultisigAction::RevokeCredential { .. } |
+            MultisigAction::UnrevokeCredential { .. } => err!(ErrorCode::MultisigActionNotExecutable),
+        }
+    }
+
+    // Approved to the threshold by the current signer set
+    fn require_approved(&self, proposal: &MultisigProposal) -> Result<()> {
+        let multisig = self.multisig.as_ref().ok_or(error!(ErrorCode::MultisigNotEnabled))?;
+        require!(
+            proposal.multisig_nonce == self.multisig_nonce,
+            ErrorCode::MultisigProposalStale
+        );
+        require!(
+            proposal.approvals.count_ones() >= multisig.threshold as u32,
+            ErrorCode::MultisigThresholdNotMet
+        );
+        Ok(())
+    }
+
+    // Approved, and for exactly `action`
+    pub fn require_approved_action(&self, proposal: &MultisigProposal, action: &MultisigAction) -> Result<()> {
+        self.require_approved(proposal)?;
+        require!(proposal.action == *action, ErrorCode::MultisigActionMismatch);
+        Ok(())
+    }
 }
 
-#[derive(Accounts)]
-pub struct StoreZKProofData<'info> {
-    #[account(
-        mut,
-        seeds = [b"encrypted_compute", encrypted_compute.authority.as_ref(), encrypted_compute.data_hash.as_bytes()],
-        bump,
-    )]
-    pub encrypted_compute: Account<'info, EncryptedCompute>,
-    #[account(mut)]
-    pub user: Signer<'info>,
+// Permissions a delegate can hold, as bits of Delegate::permissions
+pub const PERMISSION_UPDATE: u8 = 1 << 0;
+pub const PERMISSION_PROCESS: u8 = 1 << 1;
+pub const PERMISSION_AUDIT: u8 = 1 << 2;
+pub const PERMISSION_PROVE: u8 = 1 << 3;
+pub const PERMISSION_ISSUE_CREDENTIAL: u8 = 1 << 4;
+pub const PERMISSION_LOCK: u8 = 1 << 5;
+pub const PERMISSION_ALL: u8 = (1 << 6) - 1;
+pub const MAX_DELEGATES: usize = 8;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct Delegate {
+    pub delegate: Pubkey,           // Key acting on the authority's behalf
+    pub permissions: u8,            // PERMISSION_* bits
+    pub expires_at: i64,            // Timestamp after which the grant lapses (0 = never)
 }
 
-#[derive(Accounts)]
-pub struct LogAuditEvent<'info> {
-    #[account(
-        mut,
-        seeds = [b"encrypted_compute", encrypted_compute.authority.as_ref(), encrypted_compute.data_hash.as_bytes()],
-        bump,
-    )]
-    pub encrypted_compute: Account<'info, EncryptedCompute>,
-    #[account(mut)]
-    pub user: Signer<'info>,
+// Ciphertext stored inline is capped to keep the account size bounded
+pub const MAX_ENCRYPTED_DATA_LEN: usize = 1024;
+// Legacy string data hashes were PDA seeds, so never longer than this
+pub const MAX_LEGACY_SEED_LEN: usize = 32;
+// Longest nonce/IV any supported scheme uses (XChaCha-style 24-byte nonces)
+pub const MAX_CIPHERTEXT_NONCE_LEN: usize = 24;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct CiphertextMetadata {
+    pub scheme: CiphertextScheme,   // Cipher the data was encrypted with
+    #[max_len(MAX_CIPHERTEXT_NONCE_LEN)]
+    pub nonce: Vec<u8>,             // Nonce/IV used for this ciphertext
+    pub key_id: [u8; 32],           // Identifies the encryption key, e.g. the MXE or recipient key
+    pub aad_digest: [u8; 32],       // sha256 of the associated data; zero when there is none
 }
 
-#[derive(Accounts)]
-pub struct CreateAuditTrail<'info> {
-    #[account(
-        mut,
-        seeds = [b"encrypted_compute", encrypted_compute.authority.as_ref(), encrypted_compute.data_hash.as_bytes()],
-        bump,
-    )]
-    pub encrypted_compute: Account<'info, EncryptedCompute>,
-    #[account(mut)]
-    pub user: Signer<'info>,
+impl CiphertextMetadata {
+    pub fn validate(&self) -> Result<()> {
+        require!(!self.nonce.is_empty(), ErrorCode::InvalidData);
+        require!(self.nonce.len() <= MAX_CIPHERTEXT_NONCE_LEN, ErrorCode::DataTooLong);
+        Ok(())
+    }
 }
 
-#[derive(Accounts)]
-pub struct VerifySelectiveDisclosure<'info> {
-    #[account(
-        mut,
-        seeds = [b"encrypted_compute", encrypted_compute.authority.as_ref(), encrypted_compute.data_hash.as_bytes()],
-        bump,
-    )]
-    pub encrypted_compute: Account<'info, EncryptedCompute>,
-    #[account(mut)]
-    pub user: Signer<'info>,
+// Larger ciphertexts are split into chunks that each fit in one transaction; finalize
+// takes every chunk as an account, which bounds how many there can be
+pub const MAX_CIPHERTEXT_CHUNK_LEN: usize = 900;
+pub const MAX_CIPHERTEXT_CHUNKS: usize = 24;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct ChunkedCiphertext {
+    pub generation: u32,            // Upload whose chunks hold the ciphertext
+    pub chunk_count: u32,           // Number of chunks, indexed from 0
+    pub total_len: u64,             // Ciphertext length in bytes
+    pub sha256: [u8; 32],           // sha256 of the concatenated chunks
 }
 
-#[derive(Accounts)]
-pub struct IssueVerifiableCredential<'info> {
-    #[account(
-        mut,
-        seeds = [b"encrypted_compute", encrypted_compute.authority.as_ref(), encrypted_compute.data_hash.as_bytes()],
-        bump,
-    )]
-    pub encrypted_compute: Account<'info, EncryptedCompute>,
-    #[account(mut)]
-    pub user: Signer<'info>,
+// Longest URI an external pointer may carry; fits CIDs and Arweave or HTTPS URLs
+pub const MAX_CIPHERTEXT_URI_LEN: usize = 200;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum DigestAlgorithm {
+    Sha256,
+    Blake3,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct ContentDigest {
+    pub algorithm: DigestAlgorithm,
+    pub value: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct ExternalCiphertext {
+    #[max_len(MAX_CIPHERTEXT_URI_LEN)]
+    pub uri: String,                // Where the ciphertext can be fetched, e.g. ipfs://<cid> or ar://<id>
+    pub digest: ContentDigest,      // Digest of the fetched bytes
+    pub size: u64,                  // Ciphertext length in bytes
+    pub scheme: CiphertextScheme,   // How the ciphertext was encrypted
+}
+
+impl ExternalCiphertext {
+    pub fn validate(&self) -> Result<()> {
+        require!(!self.uri.is_empty(), ErrorCode::InvalidCiphertextPointer);
+        require!(self.uri.len() <= MAX_CIPHERTEXT_URI_LEN, ErrorCode::DataTooLong);
+        // An all-zero digest binds nothing
+        require!(self.digest.value != [0u8; 32], ErrorCode::InvalidCiphertextPointer);
+        require!(self.size > 0, ErrorCode::InvalidCiphertextPointer);
+        Ok(())
+    }
+
+    // Check fetched content against the recorded digest. Only sha256 can be recomputed
+    // on-chain; blake3 digests are checked by clients.
+    pub fn matches_sha256(&self, content: &[u8]) -> bool {
+        self.digest.algorithm == DigestAlgorithm::Sha256
+            && content.len() as u64 == self.size
+            && solana_sha256_hasher::hash(content).to_bytes() == self.digest.value
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub enum CiphertextStorage {
+    Inline,                         // In `encrypted_data`
+    Chunked(ChunkedCiphertext),     // Across CiphertextChunk accounts
+    External(ExternalCiphertext),   // Off-chain, bound by its digest
 }
 
 #[account]
-pub struct EncryptedCompute {
-    pub authority: Pubkey,           // The owner of this account
-    pub data_hash: String,          // Hash of the original data
-    // encrypted_data: Storing large encrypted data directly on-chain is expensive and limited.
-    // Consider storing a reference (e.g., IPFS CID) here and the actual data off-chain.
-    pub encrypted_data: String,     // Encrypted data stored on-chain
-    pub status: EncryptedComputeStatus,             // Current status of computation
-    pub created_at: i64,            // Timestamp when account was created
-    pub updated_at: i64,            // Timestamp when account was last updated
-    pub time_lock_expiration: i64,  // Timestamp when data becomes available (0 if no time lock)
-    pub is_locked: bool,            // Whether the data is currently locked
-    pub unlock_conditions_met: bool,// Whether unlock conditions have been met
+pub struct CiphertextChunk {
+    pub encrypted_compute: Pubkey,   // The account this chunk belongs to
+    pub generation: u32,            // Upload the chunk was staged in
+    pub index: u32,                 // Position within the ciphertext
+    pub data: Vec<u8>,              // Chunk bytes
+    pub bump: u8,                   // PDA bump seed
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+impl CiphertextChunk {
+    // discriminator + encrypted_compute + generation + index + data + bump
+    pub const LEN: usize = 8 + 32 + 4 + 4 + 4 + MAX_CIPHERTEXT_CHUNK_LEN + 1;
+}
+
+// Multisig signers are tracked as bits of MultisigProposal::approvals
+pub const MAX_MULTISIG_SIGNERS: usize = 10;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct AuthorityMultisig {
+    #[max_len(MAX_MULTISIG_SIGNERS)]
+    pub signers: Vec<Pubkey>,       // Keys that may propose, approve and execute
+    pub threshold: u8,              // Approvals required to execute a proposal
+}
+
+impl AuthorityMultisig {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            !self.signers.is_empty() && self.signers.len() <= MAX_MULTISIG_SIGNERS,
+            ErrorCode::InvalidMultisig
+        );
+        require!(
+            self.threshold >= 1 && self.threshold as usize <= self.signers.len(),
+            ErrorCode::InvalidMultisig
+        );
+        for (i, signer) in self.signers.iter().enumerate() {
+            require!(
+                !self.signers[..i].contains(signer),
+                ErrorCode::InvalidMultisig
+            );
+        }
+        Ok(())
+    }
+
+    pub fn signer_index(&self, signer: &Pubkey) -> Option<usize> {
+        self.signers.iter().position(|s| s == signer)
+    }
+}
+
+// Privileged operations a multisig-governed account performs through proposals
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum MultisigAction {
+    UpdateEncryptedData { encrypted_data: Vec<u8>, metadata: CiphertextMetadata },
+    RevokeTimeLock,
+    CloseEncryptedCompute,
+    // Replace the signer set, or remove it to return to single-authority control
+    SetMultisig { multisig: Option<AuthorityMultisig> },
+    ApplyTimeLock { expiration: i64 },
+    CommitTimeLock,
+    ExtendTimeLock { new_expiration: i64 },
+    ShortenTimeLock { new_expiration: i64 },
+    SetUnlockPolicy { unlock_policy: Option<UnlockPolicy> },
+    SetTimeLockGuards { lock_guards: u16 },
+    GrantPermission { delegate: Pubkey, permissions: u8, expires_at: Option<i64> },
+    RevokePermission { delegate: Pubkey },
+    BeginCiphertextUpload,
+    SetExternalCiphertext { pointer: ExternalCiphertext },
+    ProposeAuthorityTransfer { new_authority: Pubkey },
+    // Run by the instruction they name, which takes the approved proposal with its accounts
+    AppendCiphertextChunk { index: u32, data: Vec<u8> },
+    FinalizeCiphertext { expected_hash: [u8; 32], metadata: CiphertextMetadata },
+    QueueComputation {
+        definition: Pubkey,
+        inputs: Vec<Pubkey>,        // Inputs after this account, in order
+        fee_mint: Option<Pubkey>,
+        fee: u64,
+        timeout: i64,
+        callback: Option<ComputationCallback>,
+    },
+    InitializeRevocationList,
+    IssueCredential { schema_id: String, attribute_commitment: [u8; 32], subject: Pubkey, expires_at: i64 },
+    RevokeCredential { index: u32 },
+    UnrevokeCredential { index: u32 },
+}
+
+impl MultisigAction {
+    // Variant tag + the largest payload
+    pub const MAX_LEN: usize = 1 + 4 + MAX_ENCRYPTED_DATA_LEN + CiphertextMetadata::INIT_SPACE;
+
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            MultisigAction::UpdateEncryptedData { encrypted_data, metadata } => {
+                require!(!encrypted_data.is_empty(), ErrorCode::InvalidData);
+                require!(encrypted_data.len() <= MAX_ENCRYPTED_DATA_LEN, ErrorCode::DataTooLong);
+                metadata.validate()?;
+            }
+            MultisigAction::SetMultisig { multisig: Some(multisig) } => multisig.validate()?,
+            MultisigAction::SetUnlockPolicy { unlock_policy: Some(policy) } => policy.validate()?,
+            MultisigAction::SetTimeLockGuards { lock_guards } => {
+                require!(lock_guards & !LOCK_GUARD_ALL == 0, ErrorCode::InvalidData);
+            }
+            MultisigAction::SetExternalCiphertext { pointer } => pointer.validate()?,
+            MultisigAction::AppendCiphertextChunk { data, .. } => {
+                require!(!data.is_empty(), ErrorCode::InvalidData);
+                require!(data.len() <= MAX_CIPHERTEXT_CHUNK_LEN, ErrorCode::DataTooLong);
+            }
+            MultisigAction::FinalizeCiphertext { metadata, .. } => metadata.validate()?,
+            MultisigAction::QueueComputation { inputs, callback, .. } => {
+                require!(inputs.len() < MAX_COMPUTATION_INPUTS, ErrorCode::DataTooLong);
+                if let Some(callback) = callback {
+                    callback.validate()?;
+                }
+            }
+            MultisigAction::IssueCredential { schema_id, .. } => {
+                require!(schema_id.len() <= MAX_SCHEMA_ID_LEN, ErrorCode::DataTooLong);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[account]
+pub struct MultisigProposal {
+    pub encrypted_compute: Pubkey,   // The account this proposal governs
+    pub proposal_id: u32,           // Sequence number, part of the PDA seeds
+    pub proposer: Pubkey,           // Signer who opened the proposal; receives its rent back
+    pub action: MultisigAction,     // What runs once the threshold is met
+    pub multisig_nonce: u32,        // Multisig configuration the approvals were collected under
+    pub approvals: u16,             // Bitmask over the multisig's signers
+    pub created_at: i64,            // Timestamp when the proposal was opened
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl MultisigProposal {
+    // discriminator + encrypted_compute + proposal_id + proposer + action + nonce + approvals + created_at + bump
+    pub const LEN: usize = 8 + 32 + 4 + 32 + MultisigAction::MAX_LEN + 4 + 2 + 8 + 1;
+
+    pub fn approve(&mut self, signer_index: usize) {
+        self.approvals |= 1 << signer_index;
+    }
+}
+
+// Unlock policies are bounded so they fit in the fixed-size EncryptedCompute account
+pub const MAX_UNLOCK_CONDITIONS: usize = 4;
+pub const MAX_UNLOCK_CLAUSES: usize = 4;
+pub const MAX_UNLOCK_APPROVERS: usize = 5;
+
+// A lock is released early once every condition of at least one clause is satisfied,
+// i.e. the policy is an OR of ANDs over its conditions.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub struct UnlockPolicy {
+    #[max_len(MAX_UNLOCK_CONDITIONS)]
+    pub conditions: Vec<UnlockCondition>,
+    #[max_len(MAX_UNLOCK_CLAUSES)]
+    pub clauses: Vec<u8>,           // Each clause is a bitmask over `conditions`
+    pub satisfied: u8,              // Bitmask of conditions recorded as satisfied
+}
+
+impl UnlockPolicy {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            !self.conditions.is_empty() && self.conditions.len() <= MAX_UNLOCK_CONDITIONS,
+            ErrorCode::InvalidUnlockPolicy
+        );
+        require!(
+            !self.clauses.is_empty() && self.clauses.len() <= MAX_UNLOCK_CLAUSES,
+            ErrorCode::InvalidUnlockPolicy
+        );
+
+        let known_conditions = (1u8 << self.conditions.len()) - 1;
+        for clause in self.clauses.iter() {
+            require!(
+                *clause != 0 && clause & !known_conditions == 0,
+                ErrorCode::InvalidUnlockPolicy
+            );
+        }
+
+        for condition in self.conditions.iter() {
+            condition.validate()?;
+        }
+        Ok(())
+    }
+
+    // Forget all recorded progress
+    pub fn reset(&mut self) {
+        self.satisfied = 0;
+        for condition in self.conditions.iter_mut() {
+            if let UnlockCondition::Approvals { approved, .. } = condition {
+                *approved = 0;
+            }
+        }
+    }
+
+    pub fn record(&mut self, index: usize, evidence: &UnlockEvidence) -> Result<()> {
+        let condition = self.conditions.get_mut(index).ok_or(error!(ErrorCode::InvalidUnlockPolicy))?;
+        if condition.record(evidence)? {
+            self.satisfied |= 1 << index;
+        }
+        Ok(())
+    }
+
+    pub fn is_satisfied(&self) -> bool {
+        self.clauses.iter().any(|clause| clause & self.satisfied == *clause)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub enum UnlockCondition {
+    // The chain has reached a slot height
+    SlotHeight { min_slot: u64 },
+    // The current time falls inside [not_before, not_after]
+    TimeWindow { not_before: i64, not_after: i64 },
+    // At least `threshold` of `approvers` have signed
+    Approvals {
+        #[max_len(MAX_UNLOCK_APPROVERS)]
+        approvers: Vec<Pubkey>,
+        threshold: u8,
+        approved: u8,
+    },
+    // A proof for this account has been verified against the named circuit
+    VerifiedProof {
+        #[max_len(MAX_CIRCUIT_ID_LEN)]
+        circuit_id: String,
+    },
+}
+
+impl UnlockCondition {
+    fn validate(&self) -> Result<()> {
+        match self {
+            UnlockCondition::SlotHeight { .. } => {}
+            UnlockCondition::TimeWindow { not_before, not_after } => {
+                require!(not_before < not_after, ErrorCode::InvalidUnlockPolicy);
+            }
+            UnlockCondition::Approvals { approvers, threshold, .. } => {
+                require!(
+                    !approvers.is_empty() && approvers.len() <= MAX_UNLOCK_APPROVERS,
+                    ErrorCode::InvalidUnlockPolicy
+                );
+                require!(
+                    *threshold >= 1 && *threshold as usize <= approvers.len(),
+                    ErrorCode::InvalidUnlockPolicy
+                );
+                for (i, approver) in approvers.iter().enumerate() {
+                    require!(!approvers[..i].contains(approver), ErrorCode::InvalidUnlockPolicy);
+                }
+            }
+            UnlockCondition::VerifiedProof { circuit_id } => {
+                require!(
+                    !circuit_id.is_empty() && circuit_id.len() <= MAX_CIRCUIT_ID_LEN,
+                    ErrorCode::InvalidUnlockPolicy
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // Apply evidence to this condition; returns whether it is now satisfied
+    fn record(&mut self, evidence: &UnlockEvidence) -> Result<bool> {
+        match self {
+            UnlockCondition::SlotHeight { min_slot } => {
+                require!(evidence.slot >= *min_slot, ErrorCode::UnlockConditionNotMet);
+                Ok(true)
+            }
+            UnlockCondition::TimeWindow { not_before, not_after } => {
+                require!(
+                    evidence.unix_timestamp >= *not_before && evidence.unix_timestamp <= *not_after,
+                    ErrorCode::UnlockConditionNotMet
+                );
+                Ok(true)
+            }
+            UnlockCondition::Approvals { approvers, threshold, approved } => {
+                let position = approvers
+                    .iter()
+                    .position(|approver| *approver == evidence.signer)
+                    .ok_or(error!(ErrorCode::Unauthorized))?;
+                *approved |= 1 << position;
+                Ok(approved.count_ones() >= *threshold as u32)
+            }
+            UnlockCondition::VerifiedProof { circuit_id } => {
+                require!(
+                    evidence.verified_circuit == Some(circuit_id.as_str()),
+                    ErrorCode::UnlockConditionNotMet
+                );
+                Ok(true)
+            }
+        }
+    }
+}
+
+// What the caller of record_unlock_condition can vouch for
+pub struct UnlockEvidence<'a> {
+    pub unix_timestamp: i64,
+    pub slot: u64,
+    pub signer: Pubkey,
+    pub verified_circuit: Option<&'a str>, // Circuit of a verified proof for this account, if supplied
+}
+
+// Lifecycle of the encrypted data. Side effects and the lock are tracked by
+// separate fields so they never overwrite the lifecycle.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
 pub enum EncryptedComputeStatus {
     Initialized,
     Updated,
     Processed,
-    TimeLocked,
-    Unlocked,
-    ProofVerified,
-    ProofStored,
-    AuditLogged,
-    AuditTrailCreated,
-    SelectiveDisclosureVerified,
-    CredentialIssued,
     Closed,
 }
 
+impl EncryptedComputeStatus {
+    pub const ALL: [EncryptedComputeStatus; 4] = [
+        EncryptedComputeStatus::Initialized,
+        EncryptedComputeStatus::Updated,
+        EncryptedComputeStatus::Processed,
+        EncryptedComputeStatus::Closed,
+    ];
+
+    // Transition table. Off-chain workers key off these values, so every move
+    // an instruction makes must be listed here.
+    pub fn can_transition_to(&self, next: &EncryptedComputeStatus) -> bool {
+        use EncryptedComputeStatus::*;
+
+        match (self, next) {
+            // Closed is terminal and Initialized is only ever set at creation
+            (Closed, _) | (_, Initialized) => false,
+            // Nothing to process before data has been written
+            (Initialized, Processed) => false,
+            // Any other state may update, process or close
+            (Initialized | Updated | Processed, Updated | Processed | Closed) => true,
+        }
+    }
+}
+
+// Schema ids name a credential type, e.g. "kyc-basic-v1"
+pub const MAX_SCHEMA_ID_LEN: usize = 32;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Credential {
+    pub issuer: Pubkey,             // EncryptedCompute account that issued the credential
+    pub index: u32,                 // Issue sequence number under the issuer, part of the PDA seeds
+    pub subject: Pubkey,            // Holder the credential is about
+    #[max_len(MAX_SCHEMA_ID_LEN)]
+    pub schema_id: String,          // Credential type the attributes follow
+    pub attribute_commitment: [u8; 32], // Hash or Merkle root of the attributes
+    pub issued_at: i64,             // Timestamp when the credential was issued
+    pub expires_at: i64,            // Timestamp after which the credential lapses (0 = never)
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl Credential {
+    // discriminator + fields at their #[max_len] bounds
+    pub const LEN: usize = 8 + Self::INIT_SPACE;
+
+    // Live until it expires or its issuer revokes it in `revocation_list`
+    pub fn require_active(&self, revocation_list: &RevocationList, now: i64) -> Result<()> {
+        require!(revocation_list.issuer == self.issuer, ErrorCode::InvalidData);
+        require!(!revocation_list.is_revoked(self.index), ErrorCode::CredentialRevoked);
+        require!(self.expires_at == 0 || now < self.expires_at, ErrorCode::CredentialExpired);
+        Ok(())
+    }
+
+    // Live and held by `subject`
+    pub fn require_valid(&self, subject: &Pubkey, revocation_list: &RevocationList, now: i64) -> Result<()> {
+        require!(self.subject == *subject, ErrorCode::Unauthorized);
+        self.require_active(revocation_list, now)
+    }
+
+    // Live, held by `subject` and issued by the `issuer` the verifier trusts
+    pub fn require_presented(&self,
+                             issuer: &Pubkey,
+                             subject: &Pubkey,
+                             revocation_list: &RevocationList,
+                             now: i64) -> Result<()> {
+        require!(self.issuer == *issuer, ErrorCode::CredentialIssuerMismatch);
+        self.require_valid(subject, revocation_list, now)
+    }
+
+    // Public input tying a disclosure proof to this credential: the attribute commitment,
+    // truncated to fit below the BN254 scalar field
+    pub fn disclosure_commitment(&self) -> [u8; groth16::FIELD_ELEMENT_LEN] {
+        let mut commitment = self.attribute_commitment;
+        commitment[0] &= 0x1f;
+        commitment
+    }
+}
+
+// Revoked indexes are stored as runs, and two runs are always separated by at least one
+// live credential, so a list never needs more than one run per two credentials
+pub const MAX_REVOKED_RUNS: usize = 32_768;
+// Every issued credential must stay revocable
+pub const MAX_CREDENTIALS_PER_ISSUER: usize = MAX_REVOKED_RUNS * 2;
+
+// `len` consecutive revoked credential indexes starting at `start`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RevokedRun {
+    pub start: u32,
+    pub len: u32,
+}
+
+impl RevokedRun {
+    fn end(&self) -> u32 {
+        self.start + self.len
+    }
+}
+
+// Revocation status of every credential an issuer has issued, compressed by run-length
+// encoding the revoked indexes: revoking a batch of consecutive credentials costs one run,
+// and an issuer that rarely revokes pays rent for a few bytes. `to_bitstring` expands it
+// for publishing as a StatusList2021 status list.
+// The list also numbers the issuer's credentials. It is left behind when the issuer closes,
+// so an account re-created at the same address carries on from the last index instead of
+// colliding with the credentials already issued.
+#[account]
+pub struct RevocationList {
+    pub issuer: Pubkey,             // EncryptedCompute account whose credentials this covers
+    pub credentials_issued: u32,    // Credentials issued under this list; the next one's index
+    pub runs: Vec<RevokedRun>,      // Revoked indexes, sorted; runs never touch
+    pub revoked_count: u32,         // Number of credentials currently revoked
+    pub updated_at: i64,            // Timestamp of the last revocation change
+    pub bump: u8,                   // PDA bump seed
+}
+
+impl RevocationList {
+    // discriminator + issuer + credentials_issued + runs_len + runs + revoked_count + updated_at + bump
+    pub const fn space(runs: usize) -> usize {
+        8 + 32 + 4 + 4 + runs * 8 + 4 + 8 + 1
+    }
+
+    // Room for any single revoke or unrevoke, each of which adds at most one run
+    pub fn space_for_change(&self) -> usize {
+        Self::space(self.runs.len() + 1)
+    }
+
+    // Claim the next credential index. Issuance stops once the list could no longer
+    // track another credential.
+    pub fn next_credential_index(&mut self) -> Result<u32> {
+        let index = self.credentials_issued;
+        require!((index as usize) < MAX_CREDENTIALS_PER_ISSUER, ErrorCode::CredentialLimitReached);
+
+        self.credentials_issued = index + 1;
+        Ok(index)
+    }
+
+    pub fn is_revoked(&self, index: u32) -> bool {
+        self.run_containing(index).is_some()
+    }
+
+    pub fn revoke(&mut self, index: u32, now: i64) -> Result<()> {
+        require!(index < self.credentials_issued, ErrorCode::InvalidData);
+        require!(!self.is_revoked(index), ErrorCode::CredentialRevoked);
+
+        // Extend the run ending just before `index` and/or the one starting just after it
+        let next = self.runs.partition_point(|run| run.start <= index);
+        let joins_previous = next > 0 && self.runs[next - 1].end() == index;
+        let joins_next = next < self.runs.len() && self.runs[next].start == index + 1;
+        match (joins_previous, joins_next) {
+            (true, true) => {
+                self.runs[next - 1].len += 1 + self.runs[next].len;
+                self.runs.remove(next);
+            }
+            (true, false) => self.runs[next - 1].len += 1,
+            (false, true) => {
+                self.runs[next].start = index;
+                self.runs[next].len += 1;
+            }
+            (false, false) => self.runs.insert(next, RevokedRun { start: index, len: 1 }),
+        }
+        self.revoked_count += 1;
+        self.updated_at = now;
+        Ok(())
+    }
+
+    pub fn unrevoke(&mut self, index: u32, now: i64) -> Result<()> {
+        let position = self.run_containing(index).ok_or(error!(ErrorCode::CredentialNotRevoked))?;
+
+        // Shrink the run from either end, or split it around `index`
+        let run = self.runs[position];
+        if run.len == 1 {
+            self.runs.remove(position);
+        } else if index == run.start {
+            self.runs[position] = RevokedRun { start: index + 1, len: run.len - 1 };
+        } else if index == run.end() - 1 {
+            self.runs[position].len -= 1;
+        } else {
+            self.runs[position].len = index - run.start;
+            self.runs.insert(position + 1, RevokedRun { start: index + 1, len: run.end() - index - 1 });
+        }
+        self.revoked_count -= 1;
+        self.updated_at = now;
+        Ok(())
+    }
+
+    // The list expanded to one bit per issued credential, set if revoked, in StatusList2021
+    // order (index 0 is the most significant bit of the first byte). Issuers gzip and
+    // base64url-encode it to publish a StatusList2021 credential.
+    pub fn to_bitstring(&self) -> Vec<u8> {
+        let mut bits = vec![0u8; (self.credentials_issued as usize).div_ceil(8)];
+        for index in self.runs.iter().flat_map(|run| run.start..run.end()) {
+            bits[index as usize / 8] |= 0x80 >> (index % 8);
+        }
+        bits
+    }
+
+    fn run_containing(&self, index: u32) -> Option<usize> {
+        let next = self.runs.partition_point(|run| run.start <= index);
+        (next > 0 && index < self.runs[next - 1].end()).then(|| next - 1)
+    }
+}
+
 #[error_code]
 pub enum ErrorCode {
     #[msg("Unauthorized access to account")]
@@ -543,4 +5330,106 @@ pub enum ErrorCode {
     InvalidZKProof,
     #[msg("Invalid access level specified")]
     InvalidAccessLevel,
+    #[msg("Invalid verifying key provided")]
+    InvalidVerifyingKey,
+    #[msg("Verifying key has been deprecated")]
+    VerifyingKeyDeprecated,
+    #[msg("Verifying key has already been superseded by a newer version")]
+    VerifyingKeySuperseded,
+    #[msg("Current audit log page is full")]
+    AuditLogPageFull,
+    #[msg("Current audit log page still has room")]
+    AuditLogPageNotFull,
+    #[msg("Audit log hash chain does not match its head")]
+    AuditChainBroken,
+    #[msg("Audit trail has no room for more members")]
+    AuditTrailFull,
+    #[msg("Status transition is not allowed")]
+    InvalidStateTransition,
+    #[msg("Operation is blocked by an active time-lock")]
+    TimeLockActive,
+    #[msg("Time-lock is committed and can only be extended")]
+    TimeLockCommitted,
+    #[msg("Invalid unlock policy")]
+    InvalidUnlockPolicy,
+    #[msg("No unlock policy is set on this account")]
+    NoUnlockPolicy,
+    #[msg("Unlock condition is not met")]
+    UnlockConditionNotMet,
+    #[msg("Too many delegates on this account")]
+    TooManyDelegates,
+    #[msg("No authority transfer is pending")]
+    NoPendingAuthorityTransfer,
+    #[msg("Invalid multisig configuration")]
+    InvalidMultisig,
+    #[msg("Account is not governed by a multisig")]
+    MultisigNotEnabled,
+    #[msg("Account is governed by a multisig; submit a proposal instead")]
+    MultisigRequired,
+    #[msg("Proposal was made under a multisig configuration that has since changed")]
+    MultisigProposalStale,
+    #[msg("Proposal has not reached the multisig threshold")]
+    MultisigThresholdNotMet,
+    #[msg("Computation request is not pending")]
+    ComputationNotPending,
+    #[msg("Computation request has timed out")]
+    ComputationTimedOut,
+    #[msg("Computation request has not timed out yet")]
+    ComputationNotTimedOut,
+    #[msg("Invalid computation definition")]
+    InvalidComputationDefinition,
+    #[msg("Computation definition has been deprecated")]
+    ComputationDefinitionDeprecated,
+    #[msg("Computation definition has already been superseded by a newer version")]
+    ComputationDefinitionSuperseded,
+    #[msg("Inputs or outputs do not match the computation definition")]
+    ComputationArityMismatch,
+    #[msg("Reported compute cost exceeds the definition's maximum")]
+    ComputeCostExceeded,
+    #[msg("Executor node is not active or does not have enough stake")]
+    ExecutorNotEligible,
+    #[msg("Executor stake is still locked")]
+    ExecutorStakeLocked,
+    #[msg("Stake accounts do not match the registry's stake asset")]
+    InvalidStakeAccounts,
+    #[msg("Invalid computation callback")]
+    InvalidCallback,
+    #[msg("Not enough compute budget left for the computation callback")]
+    CallbackComputeBudgetTooLow,
+    #[msg("Fee mint is not whitelisted")]
+    FeeMintNotAllowed,
+    #[msg("Fee accounts do not match the request's fee asset")]
+    InvalidFeeAccounts,
+    #[msg("No ciphertext upload is in progress")]
+    NoCiphertextUpload,
+    #[msg("Ciphertext chunks do not match the expected hash")]
+    CiphertextHashMismatch,
+    #[msg("Ciphertext chunk is still part of the account's data")]
+    CiphertextChunkInUse,
+    #[msg("Invalid external ciphertext pointer")]
+    InvalidCiphertextPointer,
+    #[msg("Account is not in a layout that can be migrated")]
+    UnsupportedAccountLayout,
+    #[msg("Credential has been revoked")]
+    CredentialRevoked,
+    #[msg("Credential has expired")]
+    CredentialExpired,
+    #[msg("Credential is not revoked")]
+    CredentialNotRevoked,
+    #[msg("Input ciphertext is not encrypted under the computation definition's scheme")]
+    CiphertextSchemeMismatch,
+    #[msg("Credential was not issued by this account")]
+    CredentialIssuerMismatch,
+    #[msg("Issuer has issued as many credentials as its revocation list can track")]
+    CredentialLimitReached,
+    #[msg("Computation request has already been claimed")]
+    ComputationAlreadyClaimed,
+    #[msg("Request doesn't show the executor failed")]
+    NoSlashingEvidence,
+    #[msg("Request is still held as slashing evidence")]
+    SlashingWindowOpen,
+    #[msg("Proposal is for a different action")]
+    MultisigActionMismatch,
+    #[msg("Proposal must be passed to the instruction it authorizes")]
+    MultisigActionNotExecutable,
 }
\ No newline at end of file
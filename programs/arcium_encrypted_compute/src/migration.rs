@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use crate::{
+    CiphertextStorage, ContentDigest, EncryptedCompute, EncryptedComputeStatus, ErrorCode,
+    ENCRYPTED_COMPUTE_VERSION, LOCK_GUARD_DEFAULT, MAX_LEGACY_SEED_LEN,
+};
+
+// The layout EncryptedCompute accounts were written in before the version byte: the one
+// deployed to devnet, and the only older layout an account can be in. It shares the
+// EncryptedCompute discriminator, so it is recognised by decoding the account and checking
+// that its seeds derive the account's address.
+// When the layout changes again, bump ENCRYPTED_COMPUTE_VERSION, add the outgoing layout
+// here and dispatch on its version byte.
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncryptedComputeStatusV0 {
+    Initialized,
+    Updated,
+    Processed,
+    TimeLocked,
+    Unlocked,
+    ProofVerified,
+    ProofStored,
+    AuditLogged,
+    AuditTrailCreated,
+    SelectiveDisclosureVerified,
+    CredentialIssued,
+    Closed,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EncryptedComputeV0 {
+    pub authority: Pubkey,
+    pub data_hash: String,
+    pub encrypted_data: String,
+    pub status: EncryptedComputeStatusV0,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub time_lock_expiration: i64,
+    pub is_locked: bool,
+    pub unlock_conditions_met: bool,
+}
+
+impl EncryptedComputeV0 {
+    // Recognise raw account data, discriminator included, stored at `key` as a v0 account
+    pub fn decode(data: &[u8], key: &Pubkey, program_id: &Pubkey) -> Result<Self> {
+        require!(
+            data.len() >= 8 && data[..8] == *EncryptedCompute::DISCRIMINATOR,
+            anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+        );
+
+        match EncryptedComputeV0::deserialize(&mut &data[8..]) {
+            Ok(v0) if derives(key, program_id, &v0.authority, v0.data_hash.as_bytes()) => Ok(v0),
+            _ => err!(ErrorCode::UnsupportedAccountLayout),
+        }
+    }
+
+    // The string data_hash was never a full digest (it had to fit in a 32-byte seed), so
+    // the authority supplies the real one. The string stays on as the address seed and the
+    // ciphertext is carried over byte for byte; its metadata is unknown until the next update.
+    pub fn upgrade(self, data_hash: ContentDigest) -> Result<EncryptedCompute> {
+        require!(data_hash.value != [0u8; 32], ErrorCode::InvalidData);
+        require!(
+            !self.data_hash.is_empty() && self.data_hash.len() <= MAX_LEGACY_SEED_LEN,
+            ErrorCode::InvalidData
+        );
+
+        // v0 overwrote the lifecycle status with the last activity; lock state lives in
+        // is_locked, so any activity status falls back to whether data was uploaded
+        let status = match self.status {
+            EncryptedComputeStatusV0::Initialized => EncryptedComputeStatus::Initialized,
+            EncryptedComputeStatusV0::Processed => EncryptedComputeStatus::Processed,
+            EncryptedComputeStatusV0::Closed => EncryptedComputeStatus::Closed,
+            _ if self.encrypted_data.is_empty() => EncryptedComputeStatus::Initialized,
+            _ => EncryptedComputeStatus::Updated,
+        };
+
+        Ok(EncryptedCompute {
+            version: ENCRYPTED_COMPUTE_VERSION,
+            authority: self.authority,
+            creator: self.authority,
+            pending_authority: None,
+            data_hash: data_hash.value,
+            hash_algorithm: data_hash.algorithm,
+            legacy_seed: self.data_hash.into_bytes(),
+            encrypted_data: self.encrypted_data.into_bytes(),
+            ciphertext_metadata: None,
+            storage: CiphertextStorage::Inline,
+            ciphertext_generation: 0,
+            ciphertext_upload_open: false,
+            pending_chunks: 0,
+            status,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            time_lock_expiration: self.time_lock_expiration,
+            is_locked: self.is_locked,
+            unlock_conditions_met: self.unlock_conditions_met,
+            time_lock_committed: false,
+            lock_guards: LOCK_GUARD_DEFAULT,
+            unlock_policy: None,
+            delegates: Vec::new(),
+            multisig: None,
+            multisig_nonce: 0,
+            multisig_proposals_created: 0,
+            time_locks_applied: 0,
+            computations_queued: 0,
+            computations_completed: 0,
+            proofs_stored: 0,
+            proofs_verified: 0,
+            audit_trails_created: 0,
+            audit_events_logged: 0,
+            disclosures_verified: 0,
+            credentials_issued: 0,
+        })
+    }
+}
+
+// Whether the EncryptedCompute seeds for `creator` and `seed` derive `key`
+fn derives(key: &Pubkey, program_id: &Pubkey, creator: &Pubkey, seed: &[u8]) -> bool {
+    Pubkey::try_find_program_address(&[b"encrypted_compute", creator.as_ref(), seed], program_id)
+        .is_some_and(|(address, _)| address == *key)
+}
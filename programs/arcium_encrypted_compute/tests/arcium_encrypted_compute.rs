@@ -0,0 +1,2748 @@
+use anchor_lang::prelude::*;
+
+mod common;
+
+use common::{program_error, TestLedger};
+
+// BN254 generators in EIP-197 encoding
+fn g1_generator() -> [u8; 64] {
+    let mut point = [0u8; 64];
+    point[31] = 1;
+    point[63] = 2;
+    point
+}
+
+fn g2_generator() -> [u8; 128] {
+    let hex = concat!(
+        "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2",
+        "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed",
+        "090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b",
+        "12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+    );
+    let mut point = [0u8; 128];
+    for (i, byte) in point.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+    }
+    point
+}
+
+fn scalar(value: u64) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[24..].copy_from_slice(&value.to_be_bytes());
+    bytes
+}
+
+fn g1_mul(point: &[u8; 64], value: &[u8; 32]) -> [u8; 64] {
+    let input = [point.as_slice(), value.as_slice()].concat();
+    solana_bn254::prelude::alt_bn128_multiplication(&input).unwrap().try_into().unwrap()
+}
+
+fn g1_add(p: &[u8; 64], q: &[u8; 64]) -> [u8; 64] {
+    let input = [p.as_slice(), q.as_slice()].concat();
+    solana_bn254::prelude::alt_bn128_addition(&input).unwrap().try_into().unwrap()
+}
+
+// With gamma == delta, A = alpha and B = beta, the proof C = -vk_x satisfies the
+// pairing equation. Real keys never share gamma and delta, but this exercises the
+// full verification path without a trusted setup.
+fn degenerate_groth16_fixture(
+    public_inputs: &[[u8; 32]],
+) -> (arcium_encrypted_compute::groth16::Groth16VerifyingKey, [u8; 256]) {
+    use arcium_encrypted_compute::groth16::{negate_g1, Groth16VerifyingKey};
+
+    let generator = g1_generator();
+    let verifying_key = Groth16VerifyingKey {
+        alpha_g1: g1_mul(&generator, &scalar(7)),
+        beta_g2: g2_generator(),
+        gamma_g2: g2_generator(),
+        delta_g2: g2_generator(),
+        ic: (0..=public_inputs.len() as u64)
+            .map(|i| g1_mul(&generator, &scalar(11 + i)))
+            .collect(),
+    };
+
+    let mut vk_x = verifying_key.ic[0];
+    for (input, point) in public_inputs.iter().zip(verifying_key.ic[1..].iter()) {
+        vk_x = g1_add(&vk_x, &g1_mul(point, input));
+    }
+
+    let mut proof = [0u8; 256];
+    proof[..64].copy_from_slice(&verifying_key.alpha_g1);
+    proof[64..192].copy_from_slice(&verifying_key.beta_g2);
+    proof[192..].copy_from_slice(&negate_g1(&vk_x).unwrap());
+
+    (verifying_key, proof)
+}
+
+#[test]
+fn test_groth16_accepts_valid_proof() {
+    let public_inputs = [scalar(42), scalar(1000)];
+    let (verifying_key, proof) = degenerate_groth16_fixture(&public_inputs);
+
+    assert!(arcium_encrypted_compute::groth16::verify(&verifying_key, &proof, &public_inputs).is_ok());
+}
+
+#[test]
+fn test_groth16_rejects_tampered_inputs() {
+    let public_inputs = [scalar(42), scalar(1000)];
+    let (verifying_key, proof) = degenerate_groth16_fixture(&public_inputs);
+
+    let tampered_inputs = [scalar(43), scalar(1000)];
+    assert!(arcium_encrypted_compute::groth16::verify(&verifying_key, &proof, &tampered_inputs).is_err());
+
+    let mut tampered_proof = proof;
+    tampered_proof[255] ^= 1;
+    assert!(arcium_encrypted_compute::groth16::verify(&verifying_key, &tampered_proof, &public_inputs).is_err());
+
+    assert!(arcium_encrypted_compute::groth16::verify(&verifying_key, &proof, &public_inputs[..1]).is_err());
+    assert!(arcium_encrypted_compute::groth16::verify(&verifying_key, &[0u8; 256], &public_inputs).is_err());
+}
+
+#[test]
+fn test_audit_entry_hash_detects_tampering() {
+    use arcium_encrypted_compute::AuditEntry;
+
+    let mut entry = AuditEntry {
+        index: 0,
+        timestamp: 1_700_000_000,
+        slot: 42,
+        actor: Pubkey::new_unique(),
+        event_type: "access".to_string(),
+        event_data: "encrypted_payload".to_string(),
+        user_address: "user".to_string(),
+        prev_hash: [0u8; 32],
+        entry_hash: [0u8; 32],
+    };
+    entry.entry_hash = entry.compute_hash();
+    assert_eq!(entry.compute_hash(), entry.entry_hash);
+
+    // Editing any field or re-pointing the chain changes the hash
+    let mut edited = entry.clone();
+    edited.event_data = "encrypted_payloaD".to_string();
+    assert_ne!(edited.compute_hash(), entry.entry_hash);
+
+    let mut relinked = entry.clone();
+    relinked.prev_hash = [1u8; 32];
+    assert_ne!(relinked.compute_hash(), entry.entry_hash);
+
+    // Moving bytes between length-prefixed fields is also detected
+    let mut shifted = entry.clone();
+    shifted.event_type = "accessencrypted_payload".to_string();
+    shifted.event_data = String::new();
+    assert_ne!(shifted.compute_hash(), entry.entry_hash);
+}
+
+#[test]
+fn test_audit_trail_access_levels() {
+    use arcium_encrypted_compute::{AuditAccessLevel, AuditTrail, AuditTrailMember, AuditTrailRole};
+
+    let owner = Pubkey::new_unique();
+    let reader = Pubkey::new_unique();
+    let auditor = Pubkey::new_unique();
+    let outsider = Pubkey::new_unique();
+
+    let mut trail = AuditTrail {
+        encrypted_compute: Pubkey::new_unique(),
+        name: "kyc".to_string(),
+        access_level: AuditAccessLevel::Public,
+        members: vec![
+            AuditTrailMember { member: reader, role: AuditTrailRole::Reader },
+            AuditTrailMember { member: auditor, role: AuditTrailRole::Auditor },
+        ],
+        created_at: 0,
+        bump: 0,
+    };
+
+    // Only the owner and auditors may append, whatever the access level
+    assert!(trail.can_append(&owner, &owner) && trail.can_append(&auditor, &owner));
+    assert!(!trail.can_append(&reader, &owner) && !trail.can_append(&outsider, &owner));
+
+    assert!(trail.can_read(&outsider, &owner));
+
+    trail.access_level = AuditAccessLevel::Restricted;
+    assert!(trail.can_read(&reader, &owner) && trail.can_read(&auditor, &owner));
+    assert!(!trail.can_read(&outsider, &owner));
+
+    trail.access_level = AuditAccessLevel::Confidential;
+    assert!(trail.can_read(&owner, &owner) && trail.can_read(&auditor, &owner));
+    assert!(!trail.can_read(&reader, &owner) && !trail.can_read(&outsider, &owner));
+
+    assert!(AuditAccessLevel::from_u8(3).is_err());
+}
+
+#[test]
+fn test_status_transition_table_covers_every_pair() {
+    use arcium_encrypted_compute::EncryptedComputeStatus;
+
+    // Rows are the current status, columns the next status, both in
+    // EncryptedComputeStatus::ALL order: Initialized, Updated, Processed, Closed
+    const EXPECTED: [[bool; 4]; 4] = [
+        [false, true,  false, true ], // Initialized
+        [false, true,  true,  true ], // Updated
+        [false, true,  true,  true ], // Processed
+        [false, false, false, false], // Closed
+    ];
+
+    for (i, from) in EncryptedComputeStatus::ALL.iter().enumerate() {
+        for (j, to) in EncryptedComputeStatus::ALL.iter().enumerate() {
+            assert_eq!(
+                from.can_transition_to(to),
+                EXPECTED[i][j],
+                "unexpected result for {:?} -> {:?}",
+                from,
+                to
+            );
+        }
+    }
+}
+
+fn sample_ciphertext_metadata() -> arcium_encrypted_compute::CiphertextMetadata {
+    arcium_encrypted_compute::CiphertextMetadata {
+        scheme: arcium_encrypted_compute::CiphertextScheme::Aes256Gcm,
+        nonce: vec![1u8; 12],
+        key_id: [2u8; 32],
+        aad_digest: [0u8; 32],
+    }
+}
+
+fn sample_encrypted_compute() -> arcium_encrypted_compute::EncryptedCompute {
+    let authority = Pubkey::new_unique();
+    arcium_encrypted_compute::EncryptedCompute {
+        version: arcium_encrypted_compute::ENCRYPTED_COMPUTE_VERSION,
+        authority,
+        creator: authority,
+        pending_authority: None,
+        data_hash: [7u8; 32],
+        hash_algorithm: arcium_encrypted_compute::DigestAlgorithm::Sha256,
+        legacy_seed: Vec::new(),
+        encrypted_data: b"encrypted_data_123".to_vec(),
+        ciphertext_metadata: Some(sample_ciphertext_metadata()),
+        storage: arcium_encrypted_compute::CiphertextStorage::Inline,
+        ciphertext_generation: 0,
+        ciphertext_upload_open: false,
+        pending_chunks: 0,
+        status: arcium_encrypted_compute::EncryptedComputeStatus::Updated,
+        created_at: 0,
+        updated_at: 0,
+        time_lock_expiration: 0,
+        is_locked: false,
+        unlock_conditions_met: false,
+        time_lock_committed: false,
+        lock_guards: arcium_encrypted_compute::LOCK_GUARD_DEFAULT,
+        unlock_policy: None,
+        delegates: Vec::new(),
+        multisig: None,
+        multisig_nonce: 0,
+        multisig_proposals_created: 0,
+        time_locks_applied: 0,
+        computations_queued: 0,
+        computations_completed: 0,
+        proofs_stored: 0,
+        proofs_verified: 0,
+        audit_trails_created: 0,
+        audit_events_logged: 0,
+        disclosures_verified: 0,
+        credentials_issued: 0,
+    }
+}
+
+#[test]
+fn test_revoke_time_lock_leaves_clean_state() {
+    let mut encrypted_compute = sample_encrypted_compute();
+    encrypted_compute.apply_time_lock(1_000, 100).unwrap();
+    assert!(encrypted_compute.time_lock_active(100));
+
+    encrypted_compute.revoke_time_lock(200).unwrap();
+    assert!(!encrypted_compute.is_locked);
+    assert!(!encrypted_compute.unlock_conditions_met);
+    assert_eq!(encrypted_compute.time_lock_expiration, 0);
+
+    // A later status check must not mistake the revoked lock for an expired one
+    assert!(!encrypted_compute.release_expired_time_lock(300));
+    assert!(!encrypted_compute.unlock_conditions_met);
+
+    // Revoking again fails, and a fresh lock can be applied
+    assert!(encrypted_compute.revoke_time_lock(300).is_err());
+    encrypted_compute.apply_time_lock(2_000, 300).unwrap();
+    assert_eq!(encrypted_compute.time_locks_applied, 2);
+}
+
+#[test]
+fn test_extend_and_shorten_time_lock() {
+    let mut encrypted_compute = sample_encrypted_compute();
+    encrypted_compute.apply_time_lock(1_000, 100).unwrap();
+
+    // Extending must move the deadline out
+    assert!(encrypted_compute.extend_time_lock(1_000, 200).is_err());
+    encrypted_compute.extend_time_lock(1_500, 200).unwrap();
+    assert_eq!(encrypted_compute.time_lock_expiration, 1_500);
+
+    // Shortening must stay in the future and before the current deadline
+    assert!(encrypted_compute.shorten_time_lock(1_600, 200).is_err());
+    assert!(encrypted_compute.shorten_time_lock(150, 200).is_err());
+    encrypted_compute.shorten_time_lock(1_200, 200).unwrap();
+    assert_eq!(encrypted_compute.time_lock_expiration, 1_200);
+
+    // Neither works once the lock has expired
+    assert!(encrypted_compute.extend_time_lock(5_000, 1_200).is_err());
+    assert!(encrypted_compute.release_expired_time_lock(1_200));
+    assert!(encrypted_compute.unlock_conditions_met && !encrypted_compute.is_locked);
+}
+
+#[test]
+fn test_committed_time_lock_can_only_be_extended() {
+    let mut encrypted_compute = sample_encrypted_compute();
+    encrypted_compute.apply_time_lock(1_000, 100).unwrap();
+    encrypted_compute.commit_time_lock(100).unwrap();
+
+    assert!(encrypted_compute.shorten_time_lock(500, 200).is_err());
+    assert!(encrypted_compute.revoke_time_lock(200).is_err());
+    encrypted_compute.extend_time_lock(2_000, 200).unwrap();
+
+    // Expiry releases the lock and clears the commitment
+    assert!(encrypted_compute.release_expired_time_lock(2_000));
+    assert!(!encrypted_compute.time_lock_committed);
+}
+
+#[test]
+fn test_unlock_policy_combines_conditions() {
+    use arcium_encrypted_compute::{UnlockCondition, UnlockEvidence, UnlockPolicy};
+
+    let approvers = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let evidence = |signer: Pubkey, slot: u64, verified_circuit: Option<&'static str>| UnlockEvidence {
+        unix_timestamp: 500,
+        slot,
+        signer,
+        verified_circuit,
+    };
+
+    // (2-of-3 approvals AND slot >= 100) OR verified range proof
+    let policy = UnlockPolicy {
+        conditions: vec![
+            UnlockCondition::Approvals { approvers: approvers.to_vec(), threshold: 2, approved: 0 },
+            UnlockCondition::SlotHeight { min_slot: 100 },
+            UnlockCondition::VerifiedProof { circuit_id: "range_proof".to_string() },
+        ],
+        clauses: vec![0b011, 0b100],
+        satisfied: 0,
+    };
+    policy.validate().unwrap();
+
+    let mut encrypted_compute = sample_encrypted_compute();
+    encrypted_compute.unlock_policy = Some(policy.clone());
+    encrypted_compute.apply_time_lock(10_000, 100).unwrap();
+
+    // Conditions that aren't met, or signers who aren't approvers, are rejected
+    let outsider = Pubkey::new_unique();
+    assert!(encrypted_compute.record_unlock_condition(0, &evidence(outsider, 50, None)).is_err());
+    assert!(encrypted_compute.record_unlock_condition(1, &evidence(outsider, 50, None)).is_err());
+    assert!(encrypted_compute.record_unlock_condition(2, &evidence(outsider, 50, Some("balance_proof"))).is_err());
+
+    // One approval plus the slot height is not enough for the first clause
+    assert!(!encrypted_compute.record_unlock_condition(0, &evidence(approvers[0], 150, None)).unwrap());
+    assert!(!encrypted_compute.record_unlock_condition(1, &evidence(outsider, 150, None)).unwrap());
+    assert!(encrypted_compute.time_lock_active(500));
+
+    // The second approval completes the clause and releases the lock
+    assert!(encrypted_compute.record_unlock_condition(0, &evidence(approvers[2], 150, None)).unwrap());
+    assert!(!encrypted_compute.is_locked && encrypted_compute.unlock_conditions_met);
+
+    // A new lock resets progress; the proof clause alone also releases it
+    encrypted_compute.apply_time_lock(10_000, 600).unwrap();
+    assert_eq!(encrypted_compute.unlock_policy.as_ref().unwrap().satisfied, 0);
+    assert!(encrypted_compute.record_unlock_condition(2, &evidence(outsider, 150, Some("range_proof"))).unwrap());
+
+    // Clauses must reference existing conditions
+    let mut invalid = policy;
+    invalid.clauses = vec![0b1000];
+    assert!(invalid.validate().is_err());
+}
+
+#[test]
+fn test_lock_guards_block_operations_until_the_lock_is_released() {
+    use arcium_encrypted_compute::{
+        UnlockCondition, UnlockEvidence, UnlockPolicy, LOCK_GUARD_CREDENTIAL, LOCK_GUARD_DISCLOSURE,
+        LOCK_GUARD_PROCESS, LOCK_GUARD_UPDATE,
+    };
+
+    let guards = [LOCK_GUARD_UPDATE, LOCK_GUARD_PROCESS, LOCK_GUARD_DISCLOSURE, LOCK_GUARD_CREDENTIAL];
+    let approver = Pubkey::new_unique();
+    let mut encrypted_compute = sample_encrypted_compute();
+    encrypted_compute.set_time_lock_guards(guards.iter().fold(0, |all, guard| all | guard), 100).unwrap();
+    encrypted_compute
+        .set_unlock_policy(
+            Some(UnlockPolicy {
+                conditions: vec![UnlockCondition::Approvals { approvers: vec![approver], threshold: 1, approved: 0 }],
+                clauses: vec![0b1],
+                satisfied: 0,
+            }),
+            100,
+        )
+        .unwrap();
+
+    // Every guarded operation waits for the lock to expire
+    encrypted_compute.apply_time_lock(1_000, 100).unwrap();
+    for guard in guards {
+        assert!(encrypted_compute.require_not_time_locked_at(guard, 999).is_err());
+        encrypted_compute.require_not_time_locked_at(guard, 1_000).unwrap();
+    }
+    assert!(encrypted_compute.release_expired_time_lock(1_000));
+    for guard in guards {
+        encrypted_compute.require_not_time_locked_at(guard, 1_000).unwrap();
+    }
+
+    // Or for the unlock policy to release it early
+    encrypted_compute.apply_time_lock(5_000, 1_000).unwrap();
+    for guard in guards {
+        assert!(encrypted_compute.require_not_time_locked_at(guard, 1_500).is_err());
+    }
+    let evidence = UnlockEvidence { unix_timestamp: 1_500, slot: 10, signer: approver, verified_circuit: None };
+    assert!(encrypted_compute.record_unlock_condition(0, &evidence).unwrap());
+    for guard in guards {
+        encrypted_compute.require_not_time_locked_at(guard, 1_500).unwrap();
+    }
+
+    // Operations left out of the guards are never blocked
+    encrypted_compute.apply_time_lock(9_000, 1_500).unwrap();
+    encrypted_compute.lock_guards = LOCK_GUARD_UPDATE;
+    assert!(encrypted_compute.require_not_time_locked_at(LOCK_GUARD_UPDATE, 2_000).is_err());
+    encrypted_compute.require_not_time_locked_at(LOCK_GUARD_PROCESS, 2_000).unwrap();
+}
+
+#[test]
+fn test_delegate_permissions_and_expiry() {
+    use arcium_encrypted_compute::{PERMISSION_AUDIT, PERMISSION_LOCK, PERMISSION_PROCESS, PERMISSION_UPDATE};
+
+    let mut encrypted_compute = sample_encrypted_compute();
+    let authority = encrypted_compute.authority;
+    let worker = Pubkey::new_unique();
+    let auditor = Pubkey::new_unique();
+
+    encrypted_compute
+        .grant_permission(worker, PERMISSION_PROCESS | PERMISSION_UPDATE, None, 100)
+        .unwrap();
+    encrypted_compute
+        .grant_permission(auditor, PERMISSION_AUDIT, Some(1_000), 100)
+        .unwrap();
+
+    // The authority can do everything, delegates only what they were granted
+    assert!(encrypted_compute.has_permission(&authority, PERMISSION_LOCK, 100));
+    assert!(encrypted_compute.has_permission(&worker, PERMISSION_PROCESS, 100));
+    assert!(!encrypted_compute.has_permission(&worker, PERMISSION_LOCK, 100));
+    assert!(!encrypted_compute.has_permission(&worker, PERMISSION_PROCESS | PERMISSION_AUDIT, 100));
+
+    // Grants lapse at their expiry
+    assert!(encrypted_compute.has_permission(&auditor, PERMISSION_AUDIT, 999));
+    assert!(!encrypted_compute.has_permission(&auditor, PERMISSION_AUDIT, 1_000));
+
+    // Re-granting replaces permissions, revoking removes the delegate
+    encrypted_compute.grant_permission(worker, PERMISSION_UPDATE, None, 100).unwrap();
+    assert!(!encrypted_compute.has_permission(&worker, PERMISSION_PROCESS, 100));
+    encrypted_compute.revoke_permission(&worker).unwrap();
+    assert!(!encrypted_compute.has_permission(&worker, PERMISSION_UPDATE, 100));
+    assert!(encrypted_compute.revoke_permission(&worker).is_err());
+
+    // Empty, unknown or already-expired grants are rejected
+    assert!(encrypted_compute.grant_permission(worker, 0, None, 100).is_err());
+    assert!(encrypted_compute.grant_permission(worker, 1 << 7, None, 100).is_err());
+    assert!(encrypted_compute.grant_permission(worker, PERMISSION_UPDATE, Some(50), 100).is_err());
+}
+
+#[test]
+fn test_authority_transfer_is_two_step() {
+    use arcium_encrypted_compute::PERMISSION_PROCESS;
+
+    let mut encrypted_compute = sample_encrypted_compute();
+    let creator = encrypted_compute.creator;
+    let new_owner = Pubkey::new_unique();
+    let worker = Pubkey::new_unique();
+    encrypted_compute
+        .grant_permission(worker, PERMISSION_PROCESS, None, 100)
+        .unwrap();
+
+    // Nothing to accept until a transfer is proposed
+    assert!(encrypted_compute.accept_authority_transfer(&new_owner, 100).is_err());
+
+    // Transfers are blocked while a time-lock holds
+    encrypted_compute.apply_time_lock(1_000, 100).unwrap();
+    assert!(encrypted_compute.propose_authority_transfer(new_owner, 100).is_err());
+    encrypted_compute.propose_authority_transfer(new_owner, 1_000).unwrap();
+
+    // Only the proposed authority can accept
+    assert!(encrypted_compute.accept_authority_transfer(&worker, 1_000).is_err());
+    encrypted_compute.accept_authority_transfer(&new_owner, 1_000).unwrap();
+
+    // The seeds key is unchanged and the previous owner's delegates are dropped
+    assert_eq!(encrypted_compute.authority, new_owner);
+    assert_eq!(encrypted_compute.creator, creator);
+    assert_eq!(encrypted_compute.pending_authority, None);
+    assert!(!encrypted_compute.has_permission(&worker, PERMISSION_PROCESS, 1_000));
+    assert!(!encrypted_compute.has_permission(&creator, PERMISSION_PROCESS, 1_000));
+}
+
+#[test]
+fn test_multisig_proposals_need_threshold_and_current_config() {
+    use arcium_encrypted_compute::{AuthorityMultisig, MultisigAction, MultisigProposal};
+
+    let signers: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let mut encrypted_compute = sample_encrypted_compute();
+    let proposal_for = |encrypted_compute: &arcium_encrypted_compute::EncryptedCompute, action| MultisigProposal {
+        encrypted_compute: Pubkey::new_unique(),
+        proposal_id: 0,
+        proposer: signers[0],
+        action,
+        multisig_nonce: encrypted_compute.multisig_nonce,
+        approvals: 0,
+        created_at: 0,
+        bump: 0,
+    };
+
+    // Duplicate signers and unreachable thresholds are rejected
+    let invalid = AuthorityMultisig { signers: vec![signers[0], signers[0]], threshold: 1 };
+    assert!(encrypted_compute.set_multisig(Some(invalid)).is_err());
+    let invalid = AuthorityMultisig { signers: signers.clone(), threshold: 4 };
+    assert!(encrypted_compute.set_multisig(Some(invalid)).is_err());
+
+    encrypted_compute
+        .set_multisig(Some(AuthorityMultisig { signers: signers.clone(), threshold: 2 }))
+        .unwrap();
+    assert!(encrypted_compute.require_single_authority().is_err());
+    assert_eq!(encrypted_compute.multisig_signer_index(&signers[2]).unwrap(), 2);
+    assert!(encrypted_compute.multisig_signer_index(&encrypted_compute.authority).is_err());
+
+    // One approval is not enough, a second one lets the update run
+    let update = MultisigAction::UpdateEncryptedData {
+        encrypted_data: b"rotated".to_vec(),
+        metadata: sample_ciphertext_metadata(),
+    };
+    let mut proposal = proposal_for(&encrypted_compute, update);
+    proposal.approve(0);
+    proposal.approve(0);
+    assert!(encrypted_compute.execute_multisig_action(&proposal, 100).is_err());
+    proposal.approve(1);
+    encrypted_compute.execute_multisig_action(&proposal, 100).unwrap();
+    assert_eq!(encrypted_compute.encrypted_data, b"rotated");
+
+    // Changing the signer set invalidates proposals approved under the old one
+    let mut revoke = proposal_for(&encrypted_compute, MultisigAction::RevokeTimeLock);
+    revoke.approve(0);
+    revoke.approve(2);
+    let mut disable = proposal_for(&encrypted_compute, MultisigAction::SetMultisig { multisig: None });
+    disable.approve(1);
+    disable.approve(2);
+    encrypted_compute.execute_multisig_action(&disable, 100).unwrap();
+    assert!(encrypted_compute.require_single_authority().is_ok());
+    encrypted_compute
+        .set_multisig(Some(AuthorityMultisig { signers: signers.clone(), threshold: 2 }))
+        .unwrap();
+    assert!(encrypted_compute.execute_multisig_action(&revoke, 100).is_err());
+
+    // Locks and delegates are governed the same way
+    let mut lock = proposal_for(&encrypted_compute, MultisigAction::ApplyTimeLock { expiration: 1_000 });
+    lock.approve(0);
+    assert!(encrypted_compute.execute_multisig_action(&lock, 100).is_err());
+    lock.approve(1);
+    encrypted_compute.execute_multisig_action(&lock, 100).unwrap();
+    assert!(encrypted_compute.time_lock_active(100));
+
+    let delegate = Pubkey::new_unique();
+    let mut grant = proposal_for(&encrypted_compute, MultisigAction::GrantPermission {
+        delegate,
+        permissions: arcium_encrypted_compute::PERMISSION_PROCESS,
+        expires_at: None,
+    });
+    grant.approve(1);
+    grant.approve(2);
+    encrypted_compute.execute_multisig_action(&grant, 100).unwrap();
+    assert!(encrypted_compute.has_permission(&delegate, arcium_encrypted_compute::PERMISSION_PROCESS, 100));
+
+    // The largest new payload still fits a proposal account
+    const { assert!(1 + 1 + arcium_encrypted_compute::UnlockPolicy::INIT_SPACE <= MultisigAction::MAX_LEN) };
+}
+
+#[test]
+fn test_privileged_instructions_defer_to_multisig() {
+    use arcium_encrypted_compute::{accounts, instruction, AuthorityMultisig, ErrorCode, PERMISSION_LOCK};
+
+    let mut account = sample_encrypted_compute();
+    let authority = account.authority;
+    account.multisig = Some(AuthorityMultisig { signers: vec![authority, Pubkey::new_unique()], threshold: 2 });
+    let (mut ledger, encrypted_compute) = ledger_with(&account);
+    let multisig_required = Err(program_error(ErrorCode::MultisigRequired));
+
+    assert_eq!(
+        ledger.process(
+            accounts::ApplyTimeLock { encrypted_compute, user: authority },
+            instruction::ApplyTimeLock { expiration_timestamp: 5_000 },
+        ),
+        multisig_required
+    );
+    assert_eq!(
+        ledger.process(
+            accounts::ManagePermissions { encrypted_compute, user: authority },
+            instruction::GrantPermission { delegate: Pubkey::new_unique(), permissions: PERMISSION_LOCK, expires_at: None },
+        ),
+        multisig_required
+    );
+    assert_eq!(
+        ledger.process(
+            accounts::SetTimeLockGuards { encrypted_compute, user: authority },
+            instruction::SetTimeLockGuards { lock_guards: 0 },
+        ),
+        multisig_required
+    );
+    assert_eq!(
+        ledger.process(
+            accounts::SetUnlockPolicy { encrypted_compute, user: authority },
+            instruction::SetUnlockPolicy { unlock_policy: None },
+        ),
+        multisig_required
+    );
+}
+
+#[test]
+fn test_guarded_instructions_run_through_approved_proposals() {
+    use arcium_encrypted_compute::{
+        accounts, instruction, AuthorityMultisig, CiphertextChunk, CiphertextScheme, CiphertextStorage,
+        ContentDigest, DigestAlgorithm, EncryptedCompute, ErrorCode, ExternalCiphertext, MultisigAction,
+        MultisigProposal, RevocationList, RevokedRun,
+    };
+
+    let mut account = sample_encrypted_compute();
+    let authority = account.authority;
+    let cosigner = Pubkey::new_unique();
+    account.multisig = Some(AuthorityMultisig { signers: vec![authority, cosigner], threshold: 2 });
+    let (mut ledger, encrypted_compute) = ledger_with(&account);
+    ledger.put_with_space(encrypted_compute, &account, EncryptedCompute::LEN);
+    ledger.fund(cosigner, 1_000_000_000);
+
+    // Store what propose_multisig_action would, then collect both signers' approvals
+    let mut next_proposal_id = 0u32;
+    let mut approved = |ledger: &mut TestLedger, action: MultisigAction| {
+        action.validate().unwrap();
+        let proposal_id = next_proposal_id;
+        next_proposal_id += 1;
+        let (proposal, bump) = Pubkey::find_program_address(
+            &[b"multisig_proposal", encrypted_compute.as_ref(), &proposal_id.to_le_bytes()],
+            &arcium_encrypted_compute::ID,
+        );
+        let multisig_nonce = ledger.get::<EncryptedCompute>(&encrypted_compute).multisig_nonce;
+        ledger.put_with_space(proposal, &MultisigProposal {
+            encrypted_compute,
+            proposal_id,
+            proposer: cosigner,
+            action,
+            multisig_nonce,
+            approvals: 0,
+            created_at: 0,
+            bump,
+        }, MultisigProposal::LEN);
+        for user in [authority, cosigner] {
+            ledger
+                .process(
+                    accounts::ApproveMultisigAction { encrypted_compute, proposal, user },
+                    instruction::ApproveMultisigAction {},
+                )
+                .unwrap();
+        }
+        proposal
+    };
+    let execute = |ledger: &mut TestLedger, proposal| {
+        ledger.process(
+            accounts::ExecuteMultisigAction { encrypted_compute, proposal, proposer: cosigner, authority, user: authority },
+            instruction::ExecuteMultisigAction {},
+        )
+    };
+    let stored = |ledger: &TestLedger| ledger.get::<EncryptedCompute>(&encrypted_compute);
+    let not_executable = Err(program_error(ErrorCode::MultisigActionNotExecutable));
+
+    // Actions on the account alone run through execute, which spends the proposal
+    assert_eq!(
+        ledger.process(
+            accounts::BeginCiphertextUpload { encrypted_compute, user: authority },
+            instruction::BeginCiphertextUpload {},
+        ),
+        Err(program_error(ErrorCode::MultisigRequired))
+    );
+    let begin = approved(&mut ledger, MultisigAction::BeginCiphertextUpload);
+    execute(&mut ledger, begin).unwrap();
+    assert!(stored(&ledger).ciphertext_upload_open);
+    assert!(!ledger.accounts.contains_key(&begin));
+
+    // Chunks are created by their instruction, which can't run here: check the proposal
+    // it would be handed, then stage the chunk directly
+    let data = b"staged chunk".to_vec();
+    let append = approved(&mut ledger, MultisigAction::AppendCiphertextChunk { index: 0, data: data.clone() });
+    let append_proposal = ledger.get::<MultisigProposal>(&append);
+    let mut staged = stored(&ledger);
+    staged
+        .require_approved_action(&append_proposal, &MultisigAction::AppendCiphertextChunk { index: 0, data: data.clone() })
+        .unwrap();
+    assert_eq!(
+        staged
+            .require_approved_action(&append_proposal, &MultisigAction::AppendCiphertextChunk { index: 0, data: b"other".to_vec() })
+            .map_err(ProgramError::from),
+        Err(program_error(ErrorCode::MultisigActionMismatch))
+    );
+    assert_eq!(execute(&mut ledger, append), not_executable);
+    staged.record_ciphertext_chunk(0).unwrap();
+    ledger.put_with_space(encrypted_compute, &staged, EncryptedCompute::LEN);
+    let (chunk, bump) = Pubkey::find_program_address(
+        &[b"ciphertext_chunk", encrypted_compute.as_ref(), &staged.ciphertext_generation.to_le_bytes(), &0u32.to_le_bytes()],
+        &arcium_encrypted_compute::ID,
+    );
+    ledger.put(chunk, &CiphertextChunk {
+        encrypted_compute,
+        generation: staged.ciphertext_generation,
+        index: 0,
+        data: data.clone(),
+        bump,
+    });
+
+    // Finalize takes its proposal with its accounts, and only one for the same arguments
+    let expected_hash = solana_sha256_hasher::hashv(&[&data]).to_bytes();
+    let finalize_action = MultisigAction::FinalizeCiphertext { expected_hash, metadata: sample_ciphertext_metadata() };
+    let finalize = |ledger: &mut TestLedger, proposal: Option<Pubkey>, proposer| {
+        ledger.process_with_remaining(
+            accounts::FinalizeCiphertext { encrypted_compute, proposal, proposer, user: authority },
+            vec![AccountMeta::new_readonly(chunk, false)],
+            instruction::FinalizeCiphertext { expected_hash, metadata: sample_ciphertext_metadata() },
+        )
+    };
+    assert_eq!(finalize(&mut ledger, None, None), Err(program_error(ErrorCode::MultisigRequired)));
+    let other_hash = approved(&mut ledger, MultisigAction::FinalizeCiphertext {
+        expected_hash: [7u8; 32],
+        metadata: sample_ciphertext_metadata(),
+    });
+    assert_eq!(
+        finalize(&mut ledger, Some(other_hash), Some(cosigner)),
+        Err(program_error(ErrorCode::MultisigActionMismatch))
+    );
+    let proposal = approved(&mut ledger, finalize_action);
+    assert_eq!(
+        finalize(&mut ledger, Some(proposal), Some(authority)),
+        Err(program_error(ErrorCode::Unauthorized))
+    );
+    assert_eq!(execute(&mut ledger, proposal), not_executable);
+    let cosigner_lamports = ledger.accounts[&cosigner].lamports;
+    finalize(&mut ledger, Some(proposal), Some(cosigner)).unwrap();
+    assert!(matches!(stored(&ledger).storage, CiphertextStorage::Chunked(_)));
+    assert!(!ledger.accounts.contains_key(&proposal));
+    assert!(ledger.accounts[&cosigner].lamports > cosigner_lamports);
+
+    let pointer = ExternalCiphertext {
+        uri: "ar://ciphertext".to_string(),
+        digest: ContentDigest { algorithm: DigestAlgorithm::Sha256, value: [1u8; 32] },
+        size: 64,
+        scheme: CiphertextScheme::Aes256Gcm,
+    };
+    let external = approved(&mut ledger, MultisigAction::SetExternalCiphertext { pointer: pointer.clone() });
+    execute(&mut ledger, external).unwrap();
+    assert_eq!(stored(&ledger).storage, CiphertextStorage::External(pointer));
+
+    let new_authority = Pubkey::new_unique();
+    let transfer = approved(&mut ledger, MultisigAction::ProposeAuthorityTransfer { new_authority });
+    execute(&mut ledger, transfer).unwrap();
+    assert_eq!(stored(&ledger).pending_authority, Some(new_authority));
+
+    // Queueing, issuing and opening the revocation list create accounts too
+    for action in [
+        MultisigAction::QueueComputation {
+            definition: Pubkey::new_unique(),
+            inputs: vec![Pubkey::new_unique()],
+            fee_mint: None,
+            fee: 1_000,
+            timeout: 60,
+            callback: None,
+        },
+        MultisigAction::IssueCredential {
+            schema_id: "kyc-v1".to_string(),
+            attribute_commitment: [3u8; 32],
+            subject: Pubkey::new_unique(),
+            expires_at: 0,
+        },
+        MultisigAction::InitializeRevocationList,
+    ] {
+        let proposal = approved(&mut ledger, action.clone());
+        let approved_proposal = ledger.get::<MultisigProposal>(&proposal);
+        stored(&ledger).require_approved_action(&approved_proposal, &action).unwrap();
+        assert_eq!(
+            stored(&ledger)
+                .require_approved_action(&approved_proposal, &MultisigAction::RevokeCredential { index: 0 })
+                .map_err(ProgramError::from),
+            Err(program_error(ErrorCode::MultisigActionMismatch))
+        );
+        assert_eq!(execute(&mut ledger, proposal), not_executable);
+    }
+
+    // Revocations resize an existing list, so they run here end to end
+    let (revocation_list, bump) = Pubkey::find_program_address(
+        &[b"revocation_list", encrypted_compute.as_ref()],
+        &arcium_encrypted_compute::ID,
+    );
+    ledger.put(revocation_list, &RevocationList { bump, ..sample_revocation_list(encrypted_compute) });
+    ledger.accounts.get_mut(&revocation_list).unwrap().lamports += 1_000_000;
+    let runs = |ledger: &TestLedger| ledger.get::<RevocationList>(&revocation_list).runs;
+
+    let revoke = approved(&mut ledger, MultisigAction::RevokeCredential { index: 2 });
+    assert_eq!(
+        ledger.process(
+            accounts::RevokeCredential {
+                encrypted_compute,
+                revocation_list,
+                proposal: Some(revoke),
+                proposer: Some(cosigner),
+                user: authority,
+                system_program: anchor_lang::system_program::ID,
+            },
+            instruction::RevokeCredential { index: 3 },
+        ),
+        Err(program_error(ErrorCode::MultisigActionMismatch))
+    );
+    ledger
+        .process(
+            accounts::RevokeCredential {
+                encrypted_compute,
+                revocation_list,
+                proposal: Some(revoke),
+                proposer: Some(cosigner),
+                user: authority,
+                system_program: anchor_lang::system_program::ID,
+            },
+            instruction::RevokeCredential { index: 2 },
+        )
+        .unwrap();
+    assert_eq!(runs(&ledger), vec![RevokedRun { start: 2, len: 1 }]);
+    assert!(!ledger.accounts.contains_key(&revoke));
+
+    let unrevoke = approved(&mut ledger, MultisigAction::UnrevokeCredential { index: 2 });
+    ledger
+        .process(
+            accounts::UnrevokeCredential {
+                encrypted_compute,
+                revocation_list,
+                proposal: Some(unrevoke),
+                proposer: Some(cosigner),
+                user: authority,
+                system_program: anchor_lang::system_program::ID,
+            },
+            instruction::UnrevokeCredential { index: 2 },
+        )
+        .unwrap();
+    assert!(runs(&ledger).is_empty());
+
+    // The new payloads fit a proposal account
+    const {
+        use arcium_encrypted_compute::{ComputationCallback, MAX_CIPHERTEXT_CHUNK_LEN, MAX_COMPUTATION_INPUTS};
+        assert!(1 + 4 + 4 + MAX_CIPHERTEXT_CHUNK_LEN <= MultisigAction::MAX_LEN);
+        assert!(1 + 32 + 4 + MAX_COMPUTATION_INPUTS * 32 + 33 + 8 + 8 + ComputationCallback::MAX_LEN <= MultisigAction::MAX_LEN);
+    };
+}
+
+fn sample_computation_definition() -> arcium_encrypted_compute::ComputationDefinition {
+    use arcium_encrypted_compute::{CiphertextScheme, ComputationSpec};
+
+    arcium_encrypted_compute::ComputationDefinition {
+        definition_id: "private_sum".to_string(),
+        version: 1,
+        spec: ComputationSpec {
+            circuit_hash: [7u8; 32],
+            input_arity: 2,
+            output_arity: 1,
+            scheme: CiphertextScheme::Rescue,
+            max_compute_cost: 1_000,
+            allowed_executors: Vec::new(),
+            result_circuit: None,
+        },
+        superseded: false,
+        deprecated: false,
+        registered_at: 0,
+        deprecated_at: 0,
+        bump: 0,
+    }
+}
+
+// A request for the sample definition, queued at 100 and timing out at 200
+fn sample_computation_request() -> arcium_encrypted_compute::ComputationRequest {
+    let definition = sample_computation_definition();
+
+    arcium_encrypted_compute::ComputationRequest {
+        encrypted_compute: Pubkey::new_unique(),
+        request_id: 0,
+        requester: Pubkey::new_unique(),
+        definition_id: definition.definition_id,
+        definition_version: definition.version,
+        inputs: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        fee: 5_000,
+        fee_mint: None,
+        status: arcium_encrypted_compute::ComputationStatus::Queued,
+        assigned_executor: None,
+        executor: None,
+        encrypted_outputs: Vec::new(),
+        compute_cost: 0,
+        result_proof: None,
+        callback: None,
+        queued_at: 100,
+        timeout_at: 200,
+        completed_at: 0,
+        slashed: false,
+        bump: 0,
+    }
+}
+
+#[test]
+fn test_computation_request_completes_or_times_out() {
+    use arcium_encrypted_compute::{ComputationStatus, MAX_COMPUTATION_OUTPUT_LEN};
+
+    let definition = sample_computation_definition();
+    let queued = sample_computation_request();
+    let executor = Pubkey::new_unique();
+
+    // Results must arrive before the timeout and fit the output bound
+    let mut request = queued.clone();
+    assert!(request.complete(&definition, executor, vec![vec![]], 10, None, 150).is_err());
+    assert!(request.complete(&definition, executor, vec![vec![0; MAX_COMPUTATION_OUTPUT_LEN + 1]], 10, None, 150).is_err());
+    assert!(request.complete(&definition, executor, vec![vec![1, 2, 3]], 10, None, 200).is_err());
+    assert!(request.require_refundable(150).is_err());
+
+    request.complete(&definition, executor, vec![vec![1, 2, 3]], 10, None, 150).unwrap();
+    assert_eq!(request.status, ComputationStatus::Completed);
+    assert_eq!(request.executor, Some(executor));
+
+    // A completed request can neither be completed again nor refunded
+    assert!(request.complete(&definition, executor, vec![vec![4]], 10, None, 160).is_err());
+    assert!(request.require_refundable(300).is_err());
+
+    // An unanswered request becomes refundable at its timeout
+    assert!(queued.require_refundable(199).is_err());
+    assert!(queued.require_refundable(200).is_ok());
+}
+
+#[test]
+fn test_computation_results_must_fit_their_definition() {
+    let mut definition = sample_computation_definition();
+    assert!(definition.spec.validate().is_ok());
+
+    // Zero arity, a missing circuit hash or no budget make a definition invalid
+    let mut invalid = definition.spec.clone();
+    invalid.input_arity = 0;
+    assert!(invalid.validate().is_err());
+    let mut invalid = definition.spec.clone();
+    invalid.circuit_hash = [0u8; 32];
+    assert!(invalid.validate().is_err());
+    let mut invalid = definition.spec.clone();
+    invalid.max_compute_cost = 0;
+    assert!(invalid.validate().is_err());
+
+    let executor = Pubkey::new_unique();
+    let mut request = sample_computation_request();
+
+    // Wrong output count, an over-budget cost or another version are rejected
+    assert!(request.complete(&definition, executor, vec![vec![1], vec![2]], 10, None, 150).is_err());
+    assert!(request.complete(&definition, executor, vec![vec![1]], 1_001, None, 150).is_err());
+    let mut rotated = definition.clone();
+    rotated.version = 2;
+    assert!(request.complete(&rotated, executor, vec![vec![1]], 10, None, 150).is_err());
+
+    // Only listed executors may submit once the definition restricts them
+    definition.spec.allowed_executors = vec![Pubkey::new_unique()];
+    assert!(request.complete(&definition, executor, vec![vec![1]], 10, None, 150).is_err());
+    definition.spec.allowed_executors.push(executor);
+    request.complete(&definition, executor, vec![vec![1]], 1_000, None, 150).unwrap();
+    assert_eq!(request.compute_cost, 1_000);
+}
+
+#[test]
+fn test_computation_inputs_must_match_definition_scheme() {
+    use arcium_encrypted_compute::{CiphertextScheme, ContentDigest, DigestAlgorithm, ExternalCiphertext};
+
+    let definition = sample_computation_definition();
+    let mut input = sample_encrypted_compute();
+
+    // The sample ciphertext is AES-GCM, the definition runs over Rescue ciphertexts
+    assert!(definition.spec.require_input(&input).is_err());
+    input.ciphertext_metadata.as_mut().unwrap().scheme = CiphertextScheme::Rescue;
+    definition.spec.require_input(&input).unwrap();
+
+    // Accounts without a recorded scheme can't be used until their ciphertext is rewritten
+    input.ciphertext_metadata = None;
+    assert!(definition.spec.require_input(&input).is_err());
+
+    // External ciphertexts carry their scheme in the pointer
+    input.set_external_ciphertext(ExternalCiphertext {
+        uri: "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string(),
+        digest: ContentDigest { algorithm: DigestAlgorithm::Sha256, value: [3u8; 32] },
+        size: 4096,
+        scheme: CiphertextScheme::Rescue,
+    }, 100).unwrap();
+    definition.spec.require_input(&input).unwrap();
+}
+
+#[test]
+fn test_executor_node_eligibility_and_slashing() {
+    use arcium_encrypted_compute::{ExecutorNode, SlashReason, EXECUTOR_INITIAL_REPUTATION, EXECUTOR_UNSTAKE_COOLDOWN};
+
+    let mut node = ExecutorNode {
+        authority: Pubkey::new_unique(),
+        stake: 0,
+        reputation: EXECUTOR_INITIAL_REPUTATION,
+        active: true,
+        results_submitted: 0,
+        times_slashed: 0,
+        registered_at: 0,
+        deregistered_at: 0,
+        bump: 0,
+    };
+
+    // Nodes need the registry's minimum stake before they may submit
+    assert!(node.require_eligible(1_000).is_err());
+    node.stake = 1_000;
+    node.require_eligible(1_000).unwrap();
+    node.record_result();
+    assert_eq!(node.results_submitted, 1);
+    assert_eq!(node.reputation, EXECUTOR_INITIAL_REPUTATION + 1);
+
+    // Each reason takes a fixed share of the remaining stake and drops the node below the minimum
+    assert_eq!(node.slash(SlashReason::MissedDeadline), 100);
+    assert_eq!(node.slash(SlashReason::InvalidResult), 450);
+    assert_eq!(node.stake, 450);
+    assert_eq!(node.times_slashed, 2);
+    assert_eq!(node.reputation, EXECUTOR_INITIAL_REPUTATION + 1 - 10 - 50);
+    assert!(node.require_eligible(1_000).is_err());
+
+    // Stake only unlocks once the node has deregistered and the cooldown has passed
+    assert!(node.require_withdrawable(i64::MAX).is_err());
+    node.active = false;
+    node.deregistered_at = 100;
+    assert!(node.require_withdrawable(100 + EXECUTOR_UNSTAKE_COOLDOWN - 1).is_err());
+    node.require_withdrawable(100 + EXECUTOR_UNSTAKE_COOLDOWN).unwrap();
+}
+
+#[test]
+fn test_slashing_evidence_comes_from_the_request() {
+    use arcium_encrypted_compute::{ResultCircuit, SlashReason, SLASHING_WINDOW};
+
+    let mut definition = sample_computation_definition();
+    let executor = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let queued = sample_computation_request();
+
+    // A claim reserves the request for one executor until it times out
+    let mut claimed = queued.clone();
+    assert!(claimed.claim(&definition, executor, 200).is_err());
+    claimed.claim(&definition, executor, 150).unwrap();
+    assert!(claimed.claim(&definition, other, 150).is_err());
+    assert!(claimed.clone().complete(&definition, other, vec![vec![1]], 10, None, 150).is_err());
+
+    // Past its timeout the claimant is slashable, and the refund waits for that or the window
+    assert!(claimed.require_slashable(&executor, SlashReason::MissedDeadline, 199).is_err());
+    assert!(claimed.require_slashable(&other, SlashReason::MissedDeadline, 200).is_err());
+    assert!(claimed.require_slashable(&executor, SlashReason::InvalidResult, 200).is_err());
+    claimed.require_slashable(&executor, SlashReason::MissedDeadline, 200).unwrap();
+    assert!(claimed.require_refundable(200).is_err());
+    claimed.require_refundable(200 + SLASHING_WINDOW).unwrap();
+    claimed.slashed = true;
+    claimed.require_refundable(200).unwrap();
+    assert!(claimed.require_slashable(&executor, SlashReason::MissedDeadline, 200).is_err());
+
+    // Nobody answers for an unclaimed request
+    assert!(queued.require_slashable(&executor, SlashReason::MissedDeadline, 200).is_err());
+    queued.require_refundable(200).unwrap();
+
+    // Definitions with a result circuit take a proof with every result, disputable for the window
+    definition.spec.result_circuit = Some(ResultCircuit { circuit_id: "private_sum_result".to_string(), key_version: 1 });
+    definition.spec.validate().unwrap();
+    let mut proven = queued.clone();
+    assert!(proven.complete(&definition, executor, vec![vec![1]], 10, None, 150).is_err());
+    proven.complete(&definition, executor, vec![vec![1]], 10, Some([1u8; 256]), 150).unwrap();
+    assert!(proven.require_slashable(&other, SlashReason::InvalidResult, 150).is_err());
+    proven.require_slashable(&executor, SlashReason::InvalidResult, 150).unwrap();
+    assert!(proven.require_closable(150).is_err());
+    proven.require_closable(150 + SLASHING_WINDOW).unwrap();
+
+    // Unproven results can't be disputed and close straight away
+    definition.spec.result_circuit = None;
+    let mut unproven = queued.clone();
+    assert!(unproven.complete(&definition, executor, vec![vec![1]], 10, Some([1u8; 256]), 150).is_err());
+    unproven.complete(&definition, executor, vec![vec![1]], 10, None, 150).unwrap();
+    assert!(unproven.require_slashable(&executor, SlashReason::InvalidResult, 150).is_err());
+    unproven.require_closable(150).unwrap();
+}
+
+#[test]
+fn test_computation_callback_instruction_and_safeguards() {
+    use anchor_lang::AnchorDeserialize;
+    use arcium_encrypted_compute::{CallbackAccount, ComputationCallback, ComputationCallbackData, MAX_CALLBACK_ACCOUNTS};
+
+    let consumer = Pubkey::new_unique();
+    let score_account = Pubkey::new_unique();
+    let config_account = Pubkey::new_unique();
+    let callback = ComputationCallback {
+        program_id: consumer,
+        discriminator: [9u8; 8],
+        accounts: vec![
+            CallbackAccount { pubkey: score_account, is_writable: true },
+            CallbackAccount { pubkey: config_account, is_writable: false },
+        ],
+        compute_units: 50_000,
+    };
+    callback.validate().unwrap();
+
+    // Calling back into this program, oversized account lists and unbounded budgets are rejected
+    let mut invalid = callback.clone();
+    invalid.program_id = arcium_encrypted_compute::ID;
+    assert!(invalid.validate().is_err());
+    let mut invalid = callback.clone();
+    invalid.accounts = vec![CallbackAccount { pubkey: score_account, is_writable: false }; MAX_CALLBACK_ACCOUNTS + 1];
+    assert!(invalid.validate().is_err());
+    let mut invalid = callback.clone();
+    invalid.compute_units = 0;
+    assert!(invalid.validate().is_err());
+
+    // The instruction carries the discriminator, the payload and only the request's signature
+    let payload = ComputationCallbackData {
+        request: Pubkey::new_unique(),
+        encrypted_compute: Pubkey::new_unique(),
+        definition_id: "credit_score".to_string(),
+        encrypted_outputs: vec![vec![1, 2, 3]],
+    };
+    let instruction = callback.instruction(&payload).unwrap();
+    assert_eq!(instruction.program_id, consumer);
+    assert_eq!(&instruction.data[..8], &[9u8; 8]);
+    assert_eq!(ComputationCallbackData::try_from_slice(&instruction.data[8..]).unwrap(), payload);
+    assert_eq!(instruction.accounts.len(), 3);
+    assert_eq!(instruction.accounts[0], AccountMeta::new_readonly(payload.request, true));
+    assert_eq!(instruction.accounts[1], AccountMeta::new(score_account, false));
+    assert_eq!(instruction.accounts[2], AccountMeta::new_readonly(config_account, false));
+}
+
+#[test]
+fn test_fee_escrow_splits_protocol_cut() {
+    use arcium_encrypted_compute::{FeeEscrow, MAX_PROTOCOL_FEE_BPS};
+
+    let mut fee_escrow = FeeEscrow {
+        request: Pubkey::new_unique(),
+        mint: None,
+        amount: 1_000_001,
+        protocol_fee_bps: 250,
+        treasury: Pubkey::new_unique(),
+        bump: 0,
+    };
+
+    // The protocol cut rounds down, so the executor never receives less than its share
+    assert_eq!(fee_escrow.release(), (975_001, 25_000));
+    assert_eq!(fee_escrow.amount, 0);
+    assert_eq!(fee_escrow.release(), (0, 0));
+
+    // Even at the maximum cut nothing overflows or is lost on large fees
+    fee_escrow.amount = u64::MAX;
+    fee_escrow.protocol_fee_bps = MAX_PROTOCOL_FEE_BPS;
+    let (executor_share, protocol_share) = fee_escrow.release();
+    assert_eq!(executor_share + protocol_share, u64::MAX);
+    assert_eq!(protocol_share, u64::MAX / 10);
+}
+
+#[test]
+fn test_chunked_ciphertext_finalizes_only_on_matching_hash() {
+    let mut encrypted_compute = sample_encrypted_compute();
+    let chunks: [&[u8]; 2] = [b"first chunk ", b"second chunk"];
+    let expected_hash = solana_sha256_hasher::hashv(&chunks).to_bytes();
+
+    // Chunks can only be recorded during an upload, and only in order
+    assert!(encrypted_compute.record_ciphertext_chunk(0).is_err());
+    encrypted_compute.begin_ciphertext_upload();
+    let generation = encrypted_compute.ciphertext_generation;
+    assert!(encrypted_compute.record_ciphertext_chunk(1).is_err());
+    encrypted_compute.record_ciphertext_chunk(0).unwrap();
+    encrypted_compute.record_ciphertext_chunk(1).unwrap();
+    assert!(encrypted_compute.chunk_in_use(generation));
+
+    // A missing chunk or a wrong hash leaves the inline data in place
+    assert!(encrypted_compute.finalize_ciphertext(&chunks[..1], expected_hash, sample_ciphertext_metadata(), 0).is_err());
+    assert!(encrypted_compute.finalize_ciphertext(&chunks, [0u8; 32], sample_ciphertext_metadata(), 0).is_err());
+    assert_eq!(encrypted_compute.encrypted_data, b"encrypted_data_123");
+
+    encrypted_compute
+        .finalize_ciphertext(&chunks, expected_hash, sample_ciphertext_metadata(), 0)
+        .unwrap();
+    let arcium_encrypted_compute::CiphertextStorage::Chunked(stored) = encrypted_compute.storage.clone() else {
+        panic!("expected chunked storage");
+    };
+    assert_eq!(stored.chunk_count, 2);
+    assert_eq!(stored.total_len, 24);
+    assert!(encrypted_compute.encrypted_data.is_empty());
+    assert!(encrypted_compute.has_ciphertext());
+    assert!(!encrypted_compute.ciphertext_upload_open);
+
+    // A new upload keeps the stored chunks until it is finalized
+    encrypted_compute.begin_ciphertext_upload();
+    assert!(encrypted_compute.chunk_in_use(generation));
+    encrypted_compute.record_ciphertext_chunk(0).unwrap();
+    let single: [&[u8]; 1] = [b"replacement"];
+    encrypted_compute
+        .finalize_ciphertext(&single, solana_sha256_hasher::hashv(&single).to_bytes(), sample_ciphertext_metadata(), 0)
+        .unwrap();
+    assert!(!encrypted_compute.chunk_in_use(generation));
+
+    // Writing inline data drops the chunked ciphertext
+    encrypted_compute
+        .set_encrypted_data(b"inline".to_vec(), sample_ciphertext_metadata(), 0)
+        .unwrap();
+    assert_eq!(encrypted_compute.storage, arcium_encrypted_compute::CiphertextStorage::Inline);
+}
+
+#[test]
+fn test_external_ciphertext_pointer_binds_digest() {
+    use arcium_encrypted_compute::{
+        CiphertextScheme, CiphertextStorage, ContentDigest, DigestAlgorithm, ExternalCiphertext,
+    };
+
+    let mut encrypted_compute = sample_encrypted_compute();
+    let content = b"ciphertext held on ipfs";
+    let pointer = ExternalCiphertext {
+        uri: "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string(),
+        digest: ContentDigest {
+            algorithm: DigestAlgorithm::Sha256,
+            value: solana_sha256_hasher::hash(content).to_bytes(),
+        },
+        size: content.len() as u64,
+        scheme: CiphertextScheme::Aes256Gcm,
+    };
+
+    // Pointers need a URI and a digest to bind
+    let mut unbound = pointer.clone();
+    unbound.digest.value = [0u8; 32];
+    assert!(encrypted_compute.set_external_ciphertext(unbound, 0).is_err());
+    let mut no_uri = pointer.clone();
+    no_uri.uri = String::new();
+    assert!(encrypted_compute.set_external_ciphertext(no_uri, 0).is_err());
+
+    encrypted_compute.set_external_ciphertext(pointer.clone(), 0).unwrap();
+    assert!(encrypted_compute.encrypted_data.is_empty());
+    assert!(encrypted_compute.has_ciphertext());
+    assert_eq!(encrypted_compute.storage, CiphertextStorage::External(pointer.clone()));
+
+    // Tampered content no longer matches the recorded digest
+    assert!(pointer.matches_sha256(content));
+    assert!(!pointer.matches_sha256(b"ciphertext held on ipfs!"));
+    assert!(!pointer.matches_sha256(b"tampered ciphertext on ipfs"));
+}
+
+// Account data as the program stores it: discriminator followed by the fields
+fn account_data(account: &impl AnchorSerialize) -> Vec<u8> {
+    use anchor_lang::Discriminator;
+
+    let mut data = arcium_encrypted_compute::EncryptedCompute::DISCRIMINATOR.to_vec();
+    account.serialize(&mut data).unwrap();
+    data
+}
+
+fn encrypted_compute_address(creator: &Pubkey, seed: &[u8]) -> Pubkey {
+    Pubkey::find_program_address(&[b"encrypted_compute", creator.as_ref(), seed], &arcium_encrypted_compute::ID).0
+}
+
+#[test]
+fn test_devnet_layout_migrates_to_current_version() {
+    use arcium_encrypted_compute::migration::{EncryptedComputeStatusV0, EncryptedComputeV0};
+    use arcium_encrypted_compute::{ContentDigest, DigestAlgorithm, EncryptedComputeStatus, ENCRYPTED_COMPUTE_VERSION};
+
+    let program_id = arcium_encrypted_compute::ID;
+    let current = sample_encrypted_compute();
+    let authority = current.authority;
+    let digest = ContentDigest { algorithm: DigestAlgorithm::Blake3, value: [9u8; 32] };
+
+    // v0: the devnet layout, addressed by the authority and the string hash
+    let v0 = EncryptedComputeV0 {
+        authority,
+        data_hash: "devnet_hash".to_string(),
+        encrypted_data: "ZW5jcnlwdGVk".to_string(),
+        status: EncryptedComputeStatusV0::ProofStored,
+        created_at: 10,
+        updated_at: 20,
+        time_lock_expiration: 30,
+        is_locked: true,
+        unlock_conditions_met: false,
+    };
+    let v0_key = encrypted_compute_address(&authority, b"devnet_hash");
+    let decoded = EncryptedComputeV0::decode(&account_data(&v0), &v0_key, &program_id).unwrap();
+    assert_eq!(decoded.authority, authority);
+
+    // The real digest has to be supplied
+    let unknown_digest = ContentDigest { algorithm: DigestAlgorithm::Sha256, value: [0u8; 32] };
+    assert!(decoded.clone().upgrade(unknown_digest).is_err());
+    let migrated = decoded.upgrade(digest).unwrap();
+    assert_eq!(migrated.version, ENCRYPTED_COMPUTE_VERSION);
+    assert_eq!(migrated.creator, authority);
+    assert_eq!(migrated.status, EncryptedComputeStatus::Updated);
+    assert!(migrated.is_locked);
+    assert_eq!(migrated.time_lock_expiration, 30);
+    assert_eq!(migrated.encrypted_data, b"ZW5jcnlwdGVk");
+    assert_eq!(migrated.data_hash, [9u8; 32]);
+    assert_eq!(migrated.hash_algorithm, DigestAlgorithm::Blake3);
+    assert!(migrated.ciphertext_metadata.is_none());
+    assert_eq!(encrypted_compute_address(&migrated.creator, migrated.address_seed()), v0_key);
+
+    // Data stored at another address, already current, or without the discriminator is not migrated
+    let current_key = encrypted_compute_address(&authority, &current.data_hash);
+    assert!(EncryptedComputeV0::decode(&account_data(&v0), &current_key, &program_id).is_err());
+    assert!(EncryptedComputeV0::decode(&account_data(&current), &current_key, &program_id).is_err());
+    assert!(EncryptedComputeV0::decode(&account_data(&v0)[1..], &v0_key, &program_id).is_err());
+
+    // Every migrated account fits the current size
+    let mut written = Vec::new();
+    migrated.try_serialize(&mut written).unwrap();
+    assert!(written.len() <= arcium_encrypted_compute::EncryptedCompute::LEN);
+}
+
+#[test]
+fn test_encrypted_compute_size_covers_largest_account() {
+    use arcium_encrypted_compute::{
+        AuthorityMultisig, CiphertextMetadata, CiphertextScheme, CiphertextStorage, ContentDigest, Delegate,
+        DigestAlgorithm, EncryptedCompute, ExternalCiphertext, UnlockCondition, UnlockPolicy, MAX_CIPHERTEXT_NONCE_LEN,
+        MAX_CIPHERTEXT_URI_LEN, MAX_DELEGATES, MAX_ENCRYPTED_DATA_LEN, MAX_LEGACY_SEED_LEN, MAX_MULTISIG_SIGNERS,
+        MAX_UNLOCK_APPROVERS, MAX_UNLOCK_CLAUSES, MAX_UNLOCK_CONDITIONS, PERMISSION_ALL,
+    };
+
+    // Every bounded field at its limit
+    let mut encrypted_compute = sample_encrypted_compute();
+    encrypted_compute.pending_authority = Some(Pubkey::new_unique());
+    encrypted_compute.legacy_seed = vec![1u8; MAX_LEGACY_SEED_LEN];
+    encrypted_compute.encrypted_data = vec![2u8; MAX_ENCRYPTED_DATA_LEN];
+    encrypted_compute.ciphertext_metadata = Some(CiphertextMetadata {
+        nonce: vec![3u8; MAX_CIPHERTEXT_NONCE_LEN],
+        ..sample_ciphertext_metadata()
+    });
+    encrypted_compute.storage = CiphertextStorage::External(ExternalCiphertext {
+        uri: "u".repeat(MAX_CIPHERTEXT_URI_LEN),
+        digest: ContentDigest { algorithm: DigestAlgorithm::Sha256, value: [4u8; 32] },
+        size: 1,
+        scheme: CiphertextScheme::Rescue,
+    });
+    encrypted_compute.unlock_policy = Some(UnlockPolicy {
+        conditions: vec![
+            UnlockCondition::Approvals {
+                approvers: vec![Pubkey::new_unique(); MAX_UNLOCK_APPROVERS],
+                threshold: 1,
+                approved: 0,
+            };
+            MAX_UNLOCK_CONDITIONS
+        ],
+        clauses: vec![1u8; MAX_UNLOCK_CLAUSES],
+        satisfied: 0,
+    });
+    encrypted_compute.delegates = vec![
+        Delegate { delegate: Pubkey::new_unique(), permissions: PERMISSION_ALL, expires_at: 0 };
+        MAX_DELEGATES
+    ];
+    encrypted_compute.multisig = Some(AuthorityMultisig {
+        signers: vec![Pubkey::new_unique(); MAX_MULTISIG_SIGNERS],
+        threshold: 1,
+    });
+
+    let mut written = Vec::new();
+    encrypted_compute.try_serialize(&mut written).unwrap();
+    assert_eq!(written.len(), EncryptedCompute::LEN);
+
+    // Changing the size changes the rent of every new account; update this deliberately
+    assert_eq!(EncryptedCompute::LEN, 2971);
+}
+
+#[test]
+fn test_oversized_ciphertext_fields_are_rejected() {
+    use arcium_encrypted_compute::{
+        CiphertextMetadata, CiphertextScheme, ContentDigest, DigestAlgorithm, ErrorCode, ExternalCiphertext,
+        MAX_CIPHERTEXT_NONCE_LEN, MAX_CIPHERTEXT_URI_LEN, MAX_ENCRYPTED_DATA_LEN,
+    };
+
+    let mut encrypted_compute = sample_encrypted_compute();
+    let data_too_long: anchor_lang::error::Error = ErrorCode::DataTooLong.into();
+
+    let oversized = encrypted_compute
+        .set_encrypted_data(vec![0u8; MAX_ENCRYPTED_DATA_LEN + 1], sample_ciphertext_metadata(), 0)
+        .unwrap_err();
+    assert_eq!(oversized, data_too_long);
+
+    let long_nonce = CiphertextMetadata { nonce: vec![0u8; MAX_CIPHERTEXT_NONCE_LEN + 1], ..sample_ciphertext_metadata() };
+    let oversized = encrypted_compute.set_encrypted_data(b"data".to_vec(), long_nonce, 0).unwrap_err();
+    assert_eq!(oversized, data_too_long);
+
+    let long_uri = ExternalCiphertext {
+        uri: "u".repeat(MAX_CIPHERTEXT_URI_LEN + 1),
+        digest: ContentDigest { algorithm: DigestAlgorithm::Sha256, value: [4u8; 32] },
+        size: 1,
+        scheme: CiphertextScheme::Aes256Gcm,
+    };
+    assert_eq!(encrypted_compute.set_external_ciphertext(long_uri, 0).unwrap_err(), data_too_long);
+
+    // Data at the limit fits
+    encrypted_compute
+        .set_encrypted_data(vec![0u8; MAX_ENCRYPTED_DATA_LEN], sample_ciphertext_metadata(), 0)
+        .unwrap();
+}
+
+// An empty revocation list for `issuer`, which has issued 16 credentials
+fn sample_revocation_list(issuer: Pubkey) -> arcium_encrypted_compute::RevocationList {
+    arcium_encrypted_compute::RevocationList {
+        issuer,
+        credentials_issued: 16,
+        runs: Vec::new(),
+        revoked_count: 0,
+        updated_at: 0,
+        bump: 255,
+    }
+}
+
+#[test]
+fn test_credential_valid_until_expired_or_revoked() {
+    use arcium_encrypted_compute::Credential;
+
+    let issuer = Pubkey::new_unique();
+    let subject = Pubkey::new_unique();
+    let credential = Credential {
+        issuer,
+        index: 9,
+        subject,
+        schema_id: "kyc-basic-v1".to_string(),
+        attribute_commitment: [5u8; 32],
+        issued_at: 100,
+        expires_at: 1_000,
+        bump: 255,
+    };
+    let mut revocation_list = sample_revocation_list(issuer);
+
+    // Only the subject can present it, and only before it expires
+    credential.require_valid(&subject, &revocation_list, 999).unwrap();
+    assert!(credential.require_valid(&Pubkey::new_unique(), &revocation_list, 999).is_err());
+    assert!(credential.require_valid(&subject, &revocation_list, 1_000).is_err());
+
+    revocation_list.revoke(9, 500).unwrap();
+    assert!(revocation_list.is_revoked(9));
+    assert!(!revocation_list.is_revoked(8));
+    assert!(revocation_list.revoke(9, 600).is_err());
+    assert!(credential.require_active(&revocation_list, 600).is_err());
+
+    // A list from another issuer says nothing about this credential
+    let other_list = sample_revocation_list(Pubkey::new_unique());
+    assert!(credential.require_active(&other_list, 600).is_err());
+
+    // Only revoked credentials can be reinstated
+    assert!(revocation_list.unrevoke(3, 700).is_err());
+    revocation_list.unrevoke(9, 700).unwrap();
+    assert_eq!(revocation_list.revoked_count, 0);
+    credential.require_valid(&subject, &revocation_list, 800).unwrap();
+
+    // Only issued indexes can be revoked
+    assert!(revocation_list.revoke(16, 900).is_err());
+}
+
+#[test]
+fn test_revocation_list_stores_runs_of_revoked_indexes() {
+    use arcium_encrypted_compute::{RevokedRun, MAX_CREDENTIALS_PER_ISSUER, MAX_REVOKED_RUNS};
+
+    let mut revocation_list = sample_revocation_list(Pubkey::new_unique());
+    let run = |start, len| RevokedRun { start, len };
+
+    // Neighbouring revocations join into one run
+    revocation_list.revoke(4, 100).unwrap();
+    revocation_list.revoke(6, 100).unwrap();
+    assert_eq!(revocation_list.runs, vec![run(4, 1), run(6, 1)]);
+    revocation_list.revoke(5, 100).unwrap();
+    revocation_list.revoke(3, 100).unwrap();
+    revocation_list.revoke(7, 100).unwrap();
+    assert_eq!(revocation_list.runs, vec![run(3, 5)]);
+    assert!((3..8).all(|index| revocation_list.is_revoked(index)));
+    assert!(!revocation_list.is_revoked(2) && !revocation_list.is_revoked(8));
+
+    // Reinstating splits a run or trims it
+    revocation_list.unrevoke(5, 200).unwrap();
+    assert_eq!(revocation_list.runs, vec![run(3, 2), run(6, 2)]);
+    revocation_list.unrevoke(3, 200).unwrap();
+    revocation_list.unrevoke(7, 200).unwrap();
+    assert_eq!(revocation_list.runs, vec![run(4, 1), run(6, 1)]);
+    assert_eq!(revocation_list.revoked_count, 2);
+
+    // Expanded, it is the StatusList2021 bitstring over every issued credential
+    assert_eq!(revocation_list.to_bitstring(), vec![0b0000_1010, 0]);
+
+    // Revoking every other credential is the worst case, and still fits
+    revocation_list.runs.clear();
+    revocation_list.revoked_count = 0;
+    revocation_list.credentials_issued = MAX_CREDENTIALS_PER_ISSUER as u32;
+    for index in (0..MAX_CREDENTIALS_PER_ISSUER as u32).step_by(2) {
+        revocation_list.revoke(index, 300).unwrap();
+    }
+    assert_eq!(revocation_list.runs.len(), MAX_REVOKED_RUNS);
+    for index in (1..MAX_CREDENTIALS_PER_ISSUER as u32).step_by(2).rev() {
+        revocation_list.revoke(index, 300).unwrap();
+    }
+    assert_eq!(revocation_list.runs, vec![run(0, MAX_CREDENTIALS_PER_ISSUER as u32)]);
+}
+
+#[test]
+fn test_credential_issuance_stops_at_revocation_list_capacity() {
+    use arcium_encrypted_compute::{ErrorCode, MAX_CREDENTIALS_PER_ISSUER};
+
+    let mut revocation_list = sample_revocation_list(Pubkey::new_unique());
+    revocation_list.credentials_issued = 0;
+    assert_eq!(revocation_list.next_credential_index().unwrap(), 0);
+    assert_eq!(revocation_list.next_credential_index().unwrap(), 1);
+    assert_eq!(revocation_list.credentials_issued, 2);
+
+    // The last index the list can hold is still issued, the one after it is not
+    revocation_list.credentials_issued = (MAX_CREDENTIALS_PER_ISSUER - 1) as u32;
+    let last = revocation_list.next_credential_index().unwrap();
+    revocation_list.revoke(last, 100).unwrap();
+    let limit: anchor_lang::error::Error = ErrorCode::CredentialLimitReached.into();
+    assert_eq!(revocation_list.next_credential_index().unwrap_err(), limit);
+    assert_eq!(revocation_list.credentials_issued as usize, MAX_CREDENTIALS_PER_ISSUER);
+}
+
+// A ledger holding `encrypted_compute` at its PDA, with its authority funded
+fn ledger_with(encrypted_compute: &arcium_encrypted_compute::EncryptedCompute) -> (TestLedger, Pubkey) {
+    let mut ledger = TestLedger::new();
+    ledger.fund(encrypted_compute.authority, 1_000_000_000);
+
+    let address = encrypted_compute_address(&encrypted_compute.creator, encrypted_compute.address_seed());
+    ledger.put(address, encrypted_compute);
+    (ledger, address)
+}
+
+// Register `key` as version `version` of `circuit_id` at its registry PDA
+fn put_verifying_key(ledger: &mut TestLedger,
+                     circuit_id: &str,
+                     version: u32,
+                     key: arcium_encrypted_compute::groth16::Groth16VerifyingKey) -> Pubkey {
+    let (address, bump) = Pubkey::find_program_address(
+        &[b"verifying_key", circuit_id.as_bytes(), &version.to_le_bytes()],
+        &arcium_encrypted_compute::ID,
+    );
+    ledger.put(address, &arcium_encrypted_compute::VerifyingKey {
+        circuit_id: circuit_id.to_string(),
+        version,
+        key,
+        superseded: false,
+        deprecated: false,
+        registered_at: 0,
+        deprecated_at: 0,
+        bump,
+    });
+    address
+}
+
+#[test]
+fn test_time_lock_instructions_enforce_authority_and_policy() {
+    use arcium_encrypted_compute::{accounts, instruction, EncryptedCompute, ErrorCode};
+
+    let account = sample_encrypted_compute();
+    let authority = account.authority;
+    let (mut ledger, encrypted_compute) = ledger_with(&account);
+    let stranger = Pubkey::new_unique();
+    ledger.fund(stranger, 1_000_000_000);
+    let lock_accounts = |user| accounts::ApplyTimeLock { encrypted_compute, user };
+
+    // Only the authority (or a lock delegate) can touch the lock
+    assert_eq!(
+        ledger.process(lock_accounts(stranger), instruction::ApplyTimeLock { expiration_timestamp: 5_000 }),
+        Err(program_error(ErrorCode::Unauthorized))
+    );
+    assert_eq!(
+        ledger.process(lock_accounts(authority), instruction::ApplyTimeLock { expiration_timestamp: 1_000 }),
+        Err(program_error(ErrorCode::InvalidTimeLock))
+    );
+    ledger.process(lock_accounts(authority), instruction::ApplyTimeLock { expiration_timestamp: 5_000 }).unwrap();
+    assert!(ledger.get::<EncryptedCompute>(&encrypted_compute).time_lock_active(ledger.unix_timestamp));
+
+    // Shortening stays in the future and before the current deadline
+    let shorten = |user| accounts::ShortenTimeLock { encrypted_compute, user };
+    assert_eq!(
+        ledger.process(shorten(stranger), instruction::ShortenTimeLock { new_expiration: 4_000 }),
+        Err(program_error(ErrorCode::Unauthorized))
+    );
+    assert_eq!(
+        ledger.process(shorten(authority), instruction::ShortenTimeLock { new_expiration: 6_000 }),
+        Err(program_error(ErrorCode::InvalidTimeLock))
+    );
+    ledger.process(shorten(authority), instruction::ShortenTimeLock { new_expiration: 4_000 }).unwrap();
+
+    // Once committed the lock can only move out
+    let commit = |user| accounts::CommitTimeLock { encrypted_compute, user };
+    assert_eq!(
+        ledger.process(commit(stranger), instruction::CommitTimeLock {}),
+        Err(program_error(ErrorCode::Unauthorized))
+    );
+    ledger.process(commit(authority), instruction::CommitTimeLock {}).unwrap();
+    assert_eq!(
+        ledger.process(shorten(authority), instruction::ShortenTimeLock { new_expiration: 3_000 }),
+        Err(program_error(ErrorCode::TimeLockCommitted))
+    );
+    assert_eq!(
+        ledger.process(accounts::RevokeTimeLock { encrypted_compute, user: authority }, instruction::RevokeTimeLock {}),
+        Err(program_error(ErrorCode::TimeLockCommitted))
+    );
+
+    let extend = |user| accounts::ExtendTimeLock { encrypted_compute, user };
+    assert_eq!(
+        ledger.process(extend(stranger), instruction::ExtendTimeLock { new_expiration: 8_000 }),
+        Err(program_error(ErrorCode::Unauthorized))
+    );
+    assert_eq!(
+        ledger.process(extend(authority), instruction::ExtendTimeLock { new_expiration: 3_500 }),
+        Err(program_error(ErrorCode::InvalidTimeLock))
+    );
+    ledger.process(extend(authority), instruction::ExtendTimeLock { new_expiration: 8_000 }).unwrap();
+    assert_eq!(ledger.get::<EncryptedCompute>(&encrypted_compute).time_lock_expiration, 8_000);
+
+    // Nothing moves an expired lock
+    ledger.unix_timestamp = 8_000;
+    assert_eq!(
+        ledger.process(extend(authority), instruction::ExtendTimeLock { new_expiration: 9_000 }),
+        Err(program_error(ErrorCode::TimeLockNotActive))
+    );
+    assert_eq!(
+        ledger.process(commit(authority), instruction::CommitTimeLock {}),
+        Err(program_error(ErrorCode::TimeLockNotActive))
+    );
+}
+
+#[test]
+fn test_revoke_time_lock_instruction_leaves_clean_state() {
+    use arcium_encrypted_compute::{accounts, instruction, EncryptedCompute, ErrorCode};
+
+    let account = sample_encrypted_compute();
+    let authority = account.authority;
+    let (mut ledger, encrypted_compute) = ledger_with(&account);
+    let stranger = Pubkey::new_unique();
+    ledger.fund(stranger, 1_000_000_000);
+    let revoke = |user| accounts::RevokeTimeLock { encrypted_compute, user };
+
+    assert_eq!(
+        ledger.process(revoke(authority), instruction::RevokeTimeLock {}),
+        Err(program_error(ErrorCode::TimeLockNotActive))
+    );
+    ledger
+        .process(
+            accounts::ApplyTimeLock { encrypted_compute, user: authority },
+            instruction::ApplyTimeLock { expiration_timestamp: 5_000 },
+        )
+        .unwrap();
+    assert_eq!(
+        ledger.process(revoke(stranger), instruction::RevokeTimeLock {}),
+        Err(program_error(ErrorCode::Unauthorized))
+    );
+    ledger.process(revoke(authority), instruction::RevokeTimeLock {}).unwrap();
+
+    let account: EncryptedCompute = ledger.get(&encrypted_compute);
+    assert!(!account.is_locked && !account.unlock_conditions_met);
+    assert_eq!(account.time_lock_expiration, 0);
+
+    // A status check afterwards must not mistake the revoked lock for an expired one
+    ledger.unix_timestamp = 6_000;
+    ledger
+        .process(
+            accounts::CheckTimeLockStatus { encrypted_compute, user: authority },
+            instruction::CheckTimeLockStatus {},
+        )
+        .unwrap();
+    assert!(!ledger.get::<EncryptedCompute>(&encrypted_compute).unlock_conditions_met);
+    assert_eq!(
+        ledger.process(revoke(authority), instruction::RevokeTimeLock {}),
+        Err(program_error(ErrorCode::TimeLockNotActive))
+    );
+}
+
+#[test]
+fn test_verifying_keys_register_and_rotate_through_versions() {
+    use arcium_encrypted_compute::{ErrorCode, VerifierRegistry, VerifyingKey, MAX_CIRCUIT_ID_LEN};
+
+    let authority = Pubkey::new_unique();
+    let registry = VerifierRegistry { authority, created_at: 0, bump: 255 };
+    registry.require_authority(&authority).unwrap();
+    assert_eq!(registry.require_authority(&Pubkey::new_unique()), Err(ErrorCode::Unauthorized.into()));
+
+    // Registration starts a circuit at version 1
+    let (key, _) = degenerate_groth16_fixture(&[scalar(1)]);
+    let first = VerifyingKey::register("range_proof".to_string(), key.clone(), 100, 254).unwrap();
+    assert_eq!((first.version, first.registered_at, first.bump), (1, 100, 254));
+    assert!(!first.superseded && !first.deprecated);
+    assert_eq!(
+        VerifyingKey::register(String::new(), key.clone(), 100, 254).err(),
+        Some(ErrorCode::InvalidData.into())
+    );
+    assert_eq!(
+        VerifyingKey::register("c".repeat(MAX_CIRCUIT_ID_LEN + 1), key.clone(), 100, 254).err(),
+        Some(ErrorCode::DataTooLong.into())
+    );
+    let mut no_constant_term = key.clone();
+    no_constant_term.ic.clear();
+    assert_eq!(
+        VerifyingKey::register("range_proof".to_string(), no_constant_term, 100, 254).err(),
+        Some(ErrorCode::InvalidVerifyingKey.into())
+    );
+
+    // Rotation bumps the version and supersedes the key it continues from, only once
+    let mut current = first;
+    let (next_key, _) = degenerate_groth16_fixture(&[scalar(1), scalar(2)]);
+    let next = current.rotate(next_key.clone(), 200, 253).unwrap();
+    assert!(current.superseded && !current.deprecated);
+    assert_eq!((next.circuit_id.as_str(), next.version, next.registered_at), ("range_proof", 2, 200));
+    assert!(next.key == next_key && !next.superseded);
+    assert_eq!(current.rotate(key, 300, 252).err(), Some(ErrorCode::VerifyingKeySuperseded.into()));
+}
+
+#[test]
+fn test_deprecated_verifying_keys_stop_accepting_proofs() {
+    use arcium_encrypted_compute::{accounts, instruction, EncryptedCompute, ErrorCode, VerifierRegistry, VerifyingKey};
+
+    let account = sample_encrypted_compute();
+    let prover = account.authority;
+    let (mut ledger, encrypted_compute) = ledger_with(&account);
+    let registry_authority = Pubkey::new_unique();
+    ledger.fund(registry_authority, 1_000_000_000);
+    let (registry, bump) = Pubkey::find_program_address(&[b"verifier_registry"], &arcium_encrypted_compute::ID);
+    ledger.put(registry, &VerifierRegistry { authority: registry_authority, created_at: 0, bump });
+
+    // A superseded version still verifies the proofs made for it
+    let public_inputs = vec![scalar(42)];
+    let (key, proof) = degenerate_groth16_fixture(&public_inputs);
+    let verifying_key = put_verifying_key(&mut ledger, "range_proof", 1, key);
+    let mut superseded: VerifyingKey = ledger.get(&verifying_key);
+    superseded.superseded = true;
+    ledger.put(verifying_key, &superseded);
+
+    let verify = |ledger: &mut TestLedger, key_version| ledger.process(
+        accounts::VerifyZKProof { encrypted_compute, verifying_key, user: prover },
+        instruction::VerifyZkProof {
+            circuit_id: "range_proof".to_string(),
+            key_version,
+            proof_data: proof,
+            public_inputs: public_inputs.clone(),
+        },
+    );
+    let deprecate = |ledger: &mut TestLedger, user| ledger.process(
+        accounts::DeprecateVerifyingKey { registry, verifying_key, user },
+        instruction::DeprecateVerifyingKey {},
+    );
+    verify(&mut ledger, 1).unwrap();
+    assert_eq!(ledger.get::<EncryptedCompute>(&encrypted_compute).proofs_verified, 1);
+    assert_eq!(verify(&mut ledger, 2), Err(program_error(ErrorCode::InvalidVerifyingKey)));
+
+    // Only the registry authority can deprecate it, not even the prover
+    assert_eq!(deprecate(&mut ledger, prover), Err(program_error(ErrorCode::Unauthorized)));
+    ledger.unix_timestamp = 1_500;
+    deprecate(&mut ledger, registry_authority).unwrap();
+    let deprecated: VerifyingKey = ledger.get(&verifying_key);
+    assert!(deprecated.deprecated);
+    assert_eq!(deprecated.deprecated_at, 1_500);
+    assert_eq!(deprecate(&mut ledger, registry_authority), Err(program_error(ErrorCode::VerifyingKeyDeprecated)));
+
+    // After which even a valid proof is refused
+    assert_eq!(verify(&mut ledger, 1), Err(program_error(ErrorCode::VerifyingKeyDeprecated)));
+    assert_eq!(ledger.get::<EncryptedCompute>(&encrypted_compute).proofs_verified, 1);
+}
+
+#[test]
+fn test_stored_proofs_check_against_their_key_version_and_refund_their_submitter() {
+    use arcium_encrypted_compute::{accounts, instruction, EncryptedCompute, ErrorCode, ZkProofRecord};
+
+    let account = sample_encrypted_compute();
+    let authority = account.authority;
+    let (mut ledger, encrypted_compute) = ledger_with(&account);
+
+    let public_inputs = [scalar(42)];
+    let (key, proof) = degenerate_groth16_fixture(&public_inputs);
+    let first_version = put_verifying_key(&mut ledger, "range_proof", 1, key);
+    let second_version = put_verifying_key(&mut ledger, "range_proof", 2, degenerate_groth16_fixture(&[scalar(42), scalar(7)]).0);
+
+    let submitter = Pubkey::new_unique();
+    ledger.fund(submitter, 1_000_000);
+    let (zk_proof_record, bump) = Pubkey::find_program_address(
+        &[b"zk_proof", encrypted_compute.as_ref(), b"proof-1"],
+        &arcium_encrypted_compute::ID,
+    );
+    let stored = ZkProofRecord {
+        encrypted_compute,
+        proof_id: "proof-1".to_string(),
+        circuit_id: "range_proof".to_string(),
+        key_version: 1,
+        proof_type: "groth16".to_string(),
+        proof_data: proof.to_vec(),
+        public_inputs: public_inputs.concat(),
+        submitter,
+        slot: 1,
+        verified: false,
+        verified_slot: 0,
+        bump,
+    };
+    ledger.put_with_space(zk_proof_record, &stored, ZkProofRecord::LEN);
+
+    let verify = |ledger: &mut TestLedger, verifying_key| ledger.process(
+        accounts::VerifyStoredZKProof { encrypted_compute, verifying_key, zk_proof_record, user: authority },
+        instruction::VerifyStoredZkProof {},
+    );
+
+    // A record only checks against the key version it was stored for
+    assert_eq!(verify(&mut ledger, second_version), Err(program_error(ErrorCode::InvalidVerifyingKey)));
+
+    // And only with a whole proof; storing one of another length fails the same check
+    let truncated = ZkProofRecord { proof_data: proof[..255].to_vec(), ..stored.clone() };
+    let verifying_key = ledger.get(&first_version);
+    assert_eq!(truncated.require_matches(&verifying_key), Err(ErrorCode::InvalidZKProof.into()));
+    ledger.put_with_space(zk_proof_record, &truncated, ZkProofRecord::LEN);
+    assert_eq!(verify(&mut ledger, first_version), Err(program_error(ErrorCode::InvalidZKProof)));
+
+    ledger.put_with_space(zk_proof_record, &stored, ZkProofRecord::LEN);
+    ledger.slot = 7;
+    verify(&mut ledger, first_version).unwrap();
+    let verified: ZkProofRecord = ledger.get(&zk_proof_record);
+    assert_eq!((verified.verified, verified.verified_slot), (true, 7));
+    assert_eq!(ledger.get::<EncryptedCompute>(&encrypted_compute).proofs_verified, 1);
+
+    // Closing is up to the authority, but the rent goes back to whoever paid for the record
+    let close = |ledger: &mut TestLedger, submitter, user| ledger.process(
+        accounts::CloseZKProofRecord { encrypted_compute, zk_proof_record, submitter, user },
+        instruction::CloseZkProofRecord {},
+    );
+    assert_eq!(close(&mut ledger, submitter, submitter), Err(program_error(ErrorCode::Unauthorized)));
+    assert_eq!(
+        close(&mut ledger, authority, authority),
+        Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintAddress).into())
+    );
+    let rent = ledger.accounts[&zk_proof_record].lamports;
+    close(&mut ledger, submitter, authority).unwrap();
+    assert!(!ledger.accounts.contains_key(&zk_proof_record));
+    assert_eq!(ledger.accounts[&submitter].lamports, 1_000_000 + rent);
+}
+
+#[test]
+fn test_guarded_instructions_wait_for_the_lock_to_release() {
+    use arcium_encrypted_compute::{
+        accounts, instruction, Credential, EncryptedCompute, ErrorCode, RevocationList, UnlockCondition,
+        UnlockPolicy, LOCK_GUARD_DISCLOSURE, LOCK_GUARD_UPDATE,
+    };
+
+    let mut account = sample_encrypted_compute();
+    account.lock_guards = LOCK_GUARD_UPDATE | LOCK_GUARD_DISCLOSURE;
+    account.unlock_policy = Some(UnlockPolicy {
+        conditions: vec![UnlockCondition::SlotHeight { min_slot: 50 }],
+        clauses: vec![0b1],
+        satisfied: 0,
+    });
+    let authority = account.authority;
+    let (mut ledger, encrypted_compute) = ledger_with(&account);
+    ledger.put_with_space(encrypted_compute, &account, EncryptedCompute::LEN);
+
+    // A credential this account issued to its own authority, not revoked
+    let (credential, credential_bump) = Pubkey::find_program_address(
+        &[b"credential", encrypted_compute.as_ref(), &0u32.to_le_bytes()],
+        &arcium_encrypted_compute::ID,
+    );
+    let (revocation_list, revocation_list_bump) = Pubkey::find_program_address(
+        &[b"revocation_list", encrypted_compute.as_ref()],
+        &arcium_encrypted_compute::ID,
+    );
+    let issued = Credential {
+        issuer: encrypted_compute,
+        index: 0,
+        subject: authority,
+        schema_id: "kyc-basic-v1".to_string(),
+        attribute_commitment: [5u8; 32],
+        issued_at: 0,
+        expires_at: 0,
+        bump: credential_bump,
+    };
+    ledger.put(credential, &issued);
+    ledger.put(revocation_list, &RevocationList {
+        bump: revocation_list_bump,
+        ..sample_revocation_list(encrypted_compute)
+    });
+    let (key, proof) = degenerate_groth16_fixture(&[issued.disclosure_commitment(), scalar(18)]);
+    let verifying_key = put_verifying_key(&mut ledger, "age_over", 1, key);
+
+    let update = |ledger: &mut TestLedger| {
+        ledger.process(
+            accounts::UpdateEncryptedData { encrypted_compute, user: authority },
+            instruction::UpdateEncryptedData {
+                encrypted_data: b"fresh_ciphertext".to_vec(),
+                metadata: sample_ciphertext_metadata(),
+            },
+        )
+    };
+    let disclose = |ledger: &mut TestLedger| {
+        ledger.process(
+            accounts::VerifySelectiveDisclosure {
+                encrypted_compute,
+                credential,
+                revocation_list,
+                verifying_key,
+                user: authority,
+            },
+            instruction::VerifySelectiveDisclosure {
+                circuit_id: "age_over".to_string(),
+                key_version: 1,
+                proof_data: proof,
+                claim_inputs: vec![scalar(18)],
+            },
+        )
+    };
+    let lock = |ledger: &mut TestLedger, expiration_timestamp| {
+        ledger
+            .process(
+                accounts::ApplyTimeLock { encrypted_compute, user: authority },
+                instruction::ApplyTimeLock { expiration_timestamp },
+            )
+            .unwrap()
+    };
+
+    // Blocked until the lock expires
+    lock(&mut ledger, 5_000);
+    assert_eq!(update(&mut ledger), Err(program_error(ErrorCode::TimeLockActive)));
+    assert_eq!(disclose(&mut ledger), Err(program_error(ErrorCode::TimeLockActive)));
+    ledger.unix_timestamp = 5_000;
+    ledger
+        .process(
+            accounts::CheckTimeLockStatus { encrypted_compute, user: authority },
+            instruction::CheckTimeLockStatus {},
+        )
+        .unwrap();
+    update(&mut ledger).unwrap();
+    disclose(&mut ledger).unwrap();
+
+    // Or until the unlock policy releases it
+    lock(&mut ledger, 9_000);
+    assert_eq!(update(&mut ledger), Err(program_error(ErrorCode::TimeLockActive)));
+    assert_eq!(disclose(&mut ledger), Err(program_error(ErrorCode::TimeLockActive)));
+    let record = |ledger: &mut TestLedger| {
+        ledger.process(
+            accounts::RecordUnlockCondition { encrypted_compute, zk_proof_record: None, user: authority },
+            instruction::RecordUnlockCondition { condition_index: 0 },
+        )
+    };
+    assert_eq!(record(&mut ledger), Err(program_error(ErrorCode::UnlockConditionNotMet)));
+    ledger.slot = 50;
+    record(&mut ledger).unwrap();
+    update(&mut ledger).unwrap();
+    disclose(&mut ledger).unwrap();
+
+    let account: EncryptedCompute = ledger.get(&encrypted_compute);
+    assert!(!account.is_locked && account.unlock_conditions_met);
+    assert_eq!(account.encrypted_data, b"fresh_ciphertext");
+    assert_eq!(account.disclosures_verified, 2);
+}
+
+#[test]
+fn test_selective_disclosure_needs_a_live_credential_issued_to_the_signer() {
+    use arcium_encrypted_compute::{
+        accounts, instruction, Credential, EncryptedCompute, ErrorCode, RevocationList, RevokedRun, VerifyingKey,
+    };
+
+    let account = sample_encrypted_compute();
+    let holder = account.authority;
+    let (mut ledger, encrypted_compute) = ledger_with(&account);
+
+    let credential_for = |issuer: Pubkey, subject: Pubkey| {
+        let (address, bump) = Pubkey::find_program_address(
+            &[b"credential", issuer.as_ref(), &0u32.to_le_bytes()],
+            &arcium_encrypted_compute::ID,
+        );
+        let credential = Credential {
+            issuer,
+            index: 0,
+            subject,
+            schema_id: "kyc-basic-v1".to_string(),
+            attribute_commitment: [5u8; 32],
+            issued_at: 0,
+            expires_at: 0,
+            bump,
+        };
+        (address, credential)
+    };
+    let revocation_list_for = |issuer: Pubkey, runs: Vec<RevokedRun>| {
+        let (address, bump) = Pubkey::find_program_address(
+            &[b"revocation_list", issuer.as_ref()],
+            &arcium_encrypted_compute::ID,
+        );
+        let revoked_count = runs.iter().map(|run| run.len).sum();
+        (address, RevocationList { runs, revoked_count, bump, ..sample_revocation_list(issuer) })
+    };
+    let (credential, issued) = credential_for(encrypted_compute, holder);
+    let (revocation_list, live) = revocation_list_for(encrypted_compute, Vec::new());
+    ledger.put(credential, &issued);
+    ledger.put(revocation_list, &live);
+
+    // The proof's first public input is the credential's attribute commitment
+    let (key, proof) = degenerate_groth16_fixture(&[issued.disclosure_commitment(), scalar(18)]);
+    let verifying_key = put_verifying_key(&mut ledger, "age_over", 1, key);
+    let disclose_with = |ledger: &mut TestLedger,
+                         credential: Pubkey,
+                         revocation_list: Pubkey,
+                         proof_data: [u8; 256],
+                         claim: u64| {
+        ledger.process(
+            accounts::VerifySelectiveDisclosure {
+                encrypted_compute,
+                credential,
+                revocation_list,
+                verifying_key,
+                user: holder,
+            },
+            instruction::VerifySelectiveDisclosure {
+                circuit_id: "age_over".to_string(),
+                key_version: 1,
+                proof_data,
+                claim_inputs: vec![scalar(claim)],
+            },
+        )
+    };
+    let disclose = |ledger: &mut TestLedger, credential: Pubkey, revocation_list: Pubkey| {
+        disclose_with(ledger, credential, revocation_list, proof, 18)
+    };
+    disclose(&mut ledger, credential, revocation_list).unwrap();
+    assert_eq!(ledger.get::<EncryptedCompute>(&encrypted_compute).disclosures_verified, 1);
+
+    // The proof doesn't carry over to a different claim, a tampered proof or a credential
+    // committing to other attributes
+    let invalid_proof = Err(program_error(ErrorCode::InvalidZKProof));
+    assert_eq!(disclose_with(&mut ledger, credential, revocation_list, proof, 21), invalid_proof);
+    let mut tampered = proof;
+    tampered[255] ^= 1;
+    assert_eq!(disclose_with(&mut ledger, credential, revocation_list, tampered, 18), invalid_proof);
+    ledger.put(credential, &Credential { attribute_commitment: [6u8; 32], ..issued.clone() });
+    assert_eq!(disclose(&mut ledger, credential, revocation_list), invalid_proof);
+    ledger.put(credential, &issued);
+
+    // Nor does a deprecated circuit key accept it
+    let mut deprecated: VerifyingKey = ledger.get(&verifying_key);
+    deprecated.deprecated = true;
+    ledger.put(verifying_key, &deprecated);
+    assert_eq!(
+        disclose(&mut ledger, credential, revocation_list),
+        Err(program_error(ErrorCode::VerifyingKeyDeprecated))
+    );
+    deprecated.deprecated = false;
+    ledger.put(verifying_key, &deprecated);
+
+    // Revoking the credential stops the disclosure
+    ledger.put(revocation_list, &revocation_list_for(encrypted_compute, vec![RevokedRun { start: 0, len: 1 }]).1);
+    assert_eq!(
+        disclose(&mut ledger, credential, revocation_list),
+        Err(program_error(ErrorCode::CredentialRevoked))
+    );
+    ledger.put(revocation_list, &live);
+
+    // So does presenting somebody else's credential
+    ledger.put(credential, &credential_for(encrypted_compute, Pubkey::new_unique()).1);
+    assert_eq!(
+        disclose(&mut ledger, credential, revocation_list),
+        Err(program_error(ErrorCode::Unauthorized))
+    );
+
+    // Or one from an issuer other than this account
+    let other_issuer = Pubkey::new_unique();
+    let (foreign_credential, foreign) = credential_for(other_issuer, holder);
+    let (foreign_list, foreign_revocations) = revocation_list_for(other_issuer, Vec::new());
+    ledger.put(foreign_credential, &foreign);
+    ledger.put(foreign_list, &foreign_revocations);
+    assert_eq!(
+        disclose(&mut ledger, foreign_credential, foreign_list),
+        Err(program_error(ErrorCode::CredentialIssuerMismatch))
+    );
+}
+
+#[test]
+fn test_closed_issuers_keep_numbering_their_credentials() {
+    use arcium_encrypted_compute::{accounts, instruction, RevocationList};
+
+    let mut issuer = sample_encrypted_compute();
+    issuer.credentials_issued = 3;
+    let authority = issuer.authority;
+    let (mut ledger, encrypted_compute) = ledger_with(&issuer);
+    let (revocation_list, bump) = Pubkey::find_program_address(
+        &[b"revocation_list", encrypted_compute.as_ref()],
+        &arcium_encrypted_compute::ID,
+    );
+    ledger.put(revocation_list, &RevocationList {
+        credentials_issued: 3,
+        bump,
+        ..sample_revocation_list(encrypted_compute)
+    });
+
+    // An issuer closes like any other account and hands its rent back
+    let rent = ledger.accounts[&encrypted_compute].lamports;
+    let balance = ledger.accounts[&authority].lamports;
+    ledger
+        .process(accounts::CloseEncryptedCompute { encrypted_compute, user: authority }, instruction::CloseEncryptedCompute {})
+        .unwrap();
+    assert!(!ledger.accounts.contains_key(&encrypted_compute));
+    assert_eq!(ledger.accounts[&authority].lamports, balance + rent);
+
+    // Its revocation list stays, so an account re-created at the address issues from index 3
+    let mut revocation_list: RevocationList = ledger.get(&revocation_list);
+    assert_eq!(revocation_list.issuer, encrypted_compute);
+    assert_eq!(revocation_list.next_credential_index().unwrap(), 3);
+}
+
+#[test]
+fn test_revocation_instructions_resize_the_list_with_its_runs() {
+    use arcium_encrypted_compute::{accounts, instruction, ErrorCode, RevocationList, RevokedRun};
+
+    let issuer = sample_encrypted_compute();
+    let authority = issuer.authority;
+    let (mut ledger, encrypted_compute) = ledger_with(&issuer);
+    let (revocation_list, bump) = Pubkey::find_program_address(
+        &[b"revocation_list", encrypted_compute.as_ref()],
+        &arcium_encrypted_compute::ID,
+    );
+    ledger.put(revocation_list, &RevocationList { bump, ..sample_revocation_list(encrypted_compute) });
+    // Pre-fund the growth; topping rent up takes a system transfer, which can't run here
+    ledger.accounts.get_mut(&revocation_list).unwrap().lamports += 1_000_000;
+    let stranger = Pubkey::new_unique();
+    ledger.fund(stranger, 1_000_000_000);
+
+    let revoke = |ledger: &mut TestLedger, user, index| {
+        ledger.process(
+            accounts::RevokeCredential {
+                encrypted_compute,
+                revocation_list,
+                proposal: None,
+                proposer: None,
+                user,
+                system_program: anchor_lang::system_program::ID,
+            },
+            instruction::RevokeCredential { index },
+        )
+    };
+    let unrevoke = |ledger: &mut TestLedger, index| {
+        ledger.process(
+            accounts::UnrevokeCredential {
+                encrypted_compute,
+                revocation_list,
+                proposal: None,
+                proposer: None,
+                user: authority,
+                system_program: anchor_lang::system_program::ID,
+            },
+            instruction::UnrevokeCredential { index },
+        )
+    };
+    let runs = |ledger: &TestLedger| ledger.get::<RevocationList>(&revocation_list).runs;
+
+    assert_eq!(revoke(&mut ledger, stranger, 2), Err(program_error(ErrorCode::Unauthorized)));
+    for index in [2, 3, 4] {
+        revoke(&mut ledger, authority, index).unwrap();
+    }
+    assert_eq!(runs(&ledger), vec![RevokedRun { start: 2, len: 3 }]);
+    assert_eq!(revoke(&mut ledger, authority, 16), Err(program_error(ErrorCode::InvalidData)));
+
+    // Splitting the run takes the extra room the list keeps for one more
+    unrevoke(&mut ledger, 3).unwrap();
+    assert_eq!(runs(&ledger), vec![RevokedRun { start: 2, len: 1 }, RevokedRun { start: 4, len: 1 }]);
+    assert_eq!(ledger.accounts[&revocation_list].data.len(), RevocationList::space(2));
+    assert_eq!(unrevoke(&mut ledger, 3), Err(program_error(ErrorCode::CredentialNotRevoked)));
+}
+
+#[test]
+fn test_audit_trail_ownership_follows_authority_transfer() {
+    use arcium_encrypted_compute::{
+        accounts, instruction, AuditAccessLevel, AuditTrail, AuditTrailMember, AuditTrailRole, ErrorCode,
+    };
+
+    let mut audited = sample_encrypted_compute();
+    let previous = audited.authority;
+    let successor = Pubkey::new_unique();
+    let auditor = Pubkey::new_unique();
+    audited.propose_authority_transfer(successor, 100).unwrap();
+    audited.accept_authority_transfer(&successor, 100).unwrap();
+
+    let (mut ledger, encrypted_compute) = ledger_with(&audited);
+    ledger.fund(previous, 1_000_000_000);
+    ledger.fund(successor, 1_000_000_000);
+    let name = "kyc".to_string();
+    let (audit_trail, bump) = Pubkey::find_program_address(
+        &[b"audit_trail", encrypted_compute.as_ref(), name.as_bytes()],
+        &arcium_encrypted_compute::ID,
+    );
+    ledger.put(audit_trail, &AuditTrail {
+        encrypted_compute,
+        name,
+        access_level: AuditAccessLevel::Confidential,
+        members: vec![AuditTrailMember { member: auditor, role: AuditTrailRole::Auditor }],
+        created_at: 0,
+        bump,
+    });
+
+    // The authority that created the trail loses it along with the account
+    let manage = |user| accounts::ManageAuditTrail { encrypted_compute, audit_trail, user };
+    let read = |reader| accounts::AuthorizeAuditTrailRead { encrypted_compute, audit_trail, reader };
+    assert_eq!(
+        ledger.process(read(previous), instruction::AuthorizeAuditTrailRead {}),
+        Err(program_error(ErrorCode::Unauthorized))
+    );
+    assert_eq!(
+        ledger.process(manage(previous), instruction::RemoveAuditTrailMember { member: auditor }),
+        Err(program_error(ErrorCode::Unauthorized))
+    );
+
+    ledger.process(read(successor), instruction::AuthorizeAuditTrailRead {}).unwrap();
+    ledger.process(manage(successor), instruction::RemoveAuditTrailMember { member: auditor }).unwrap();
+    assert!(ledger.get::<AuditTrail>(&audit_trail).members.is_empty());
+}
+
+#[test]
+fn test_audit_log_chain_verifies_across_pages_and_catches_tampering() {
+    use arcium_encrypted_compute::{
+        accounts, instruction, AuditAccessLevel, AuditLog, AuditLogPage, AuditTrail, ErrorCode, AUDIT_ENTRIES_PER_PAGE,
+    };
+
+    let account = sample_encrypted_compute();
+    let authority = account.authority;
+    let (mut ledger, encrypted_compute) = ledger_with(&account);
+    let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &arcium_encrypted_compute::ID);
+
+    let name = "kyc".to_string();
+    let (audit_trail, bump) = pda(&[b"audit_trail", encrypted_compute.as_ref(), name.as_bytes()]);
+    ledger.put(audit_trail, &AuditTrail {
+        encrypted_compute,
+        name,
+        access_level: AuditAccessLevel::Confidential,
+        members: Vec::new(),
+        created_at: 0,
+        bump,
+    });
+    let (audit_log, bump) = pda(&[b"audit_log", audit_trail.as_ref()]);
+    ledger.put(audit_log, &AuditLog { audit_trail, entry_count: 0, page_count: 0, head_hash: [0u8; 32], bump });
+
+    // Pages are written the way opening one leaves them
+    let open_page = |ledger: &mut TestLedger| {
+        let mut log: AuditLog = ledger.get(&audit_log);
+        let (page, bump) = pda(&[b"audit_log_page", audit_log.as_ref(), &log.page_count.to_le_bytes()]);
+        ledger.put_with_space(page, &AuditLogPage {
+            audit_log,
+            page_index: log.page_count,
+            entries: Vec::new(),
+            bump,
+        }, AuditLogPage::LEN);
+        log.page_count += 1;
+        ledger.put(audit_log, &log);
+        page
+    };
+    let log_event = |ledger: &mut TestLedger, audit_log_page, event_data: String| ledger.process(
+        accounts::LogAuditEvent { encrypted_compute, audit_trail, audit_log, audit_log_page, user: authority },
+        instruction::LogAuditEvent { event_type: "access".to_string(), event_data, user_address: "user".to_string() },
+    );
+    let verify = |ledger: &mut TestLedger, start_page, pages: &[Pubkey]| ledger.process_with_remaining(
+        accounts::VerifyAuditLogChain { audit_log },
+        pages.iter().map(|page| AccountMeta::new_readonly(*page, false)).collect(),
+        instruction::VerifyAuditLogChain { start_page },
+    );
+
+    // Fill the first page, then carry the chain over onto a second
+    let first_page = open_page(&mut ledger);
+    for i in 0..AUDIT_ENTRIES_PER_PAGE {
+        log_event(&mut ledger, first_page, format!("event-{i}")).unwrap();
+    }
+    // A full page is no longer the log's current one
+    assert_eq!(
+        log_event(&mut ledger, first_page, "overflow".to_string()),
+        Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintSeeds).into())
+    );
+    let second_page = open_page(&mut ledger);
+    log_event(&mut ledger, second_page, "event-8".to_string()).unwrap();
+    log_event(&mut ledger, second_page, "event-9".to_string()).unwrap();
+
+    let second: AuditLogPage = ledger.get(&second_page);
+    let first: AuditLogPage = ledger.get(&first_page);
+    assert_eq!(second.entries[0].index, AUDIT_ENTRIES_PER_PAGE as u64);
+    assert_eq!(second.entries[0].prev_hash, first.entries[AUDIT_ENTRIES_PER_PAGE - 1].entry_hash);
+    assert_eq!(ledger.get::<AuditLog>(&audit_log).head_hash, second.entries[1].entry_hash);
+
+    // The whole log verifies, and so does its tail on its own
+    verify(&mut ledger, 0, &[first_page, second_page]).unwrap();
+    verify(&mut ledger, 1, &[second_page]).unwrap();
+
+    // Pages out of order, or a page left out, break the chain
+    let broken = Err(program_error(ErrorCode::AuditChainBroken));
+    assert_eq!(verify(&mut ledger, 0, &[second_page, first_page]), broken);
+    assert_eq!(verify(&mut ledger, 0, &[first_page]), broken);
+
+    // So does editing an entry, even with its hash recomputed
+    let mut tampered = first.clone();
+    tampered.entries[3].event_data = "forged".to_string();
+    ledger.put_with_space(first_page, &tampered, AuditLogPage::LEN);
+    assert_eq!(verify(&mut ledger, 0, &[first_page, second_page]), broken);
+    tampered.entries[3].entry_hash = tampered.entries[3].compute_hash();
+    ledger.put_with_space(first_page, &tampered, AuditLogPage::LEN);
+    assert_eq!(verify(&mut ledger, 0, &[first_page, second_page]), broken);
+
+    // Or swapping two entries
+    let mut reordered = first.clone();
+    reordered.entries.swap(1, 2);
+    ledger.put_with_space(first_page, &reordered, AuditLogPage::LEN);
+    assert_eq!(verify(&mut ledger, 0, &[first_page, second_page]), broken);
+
+    ledger.put_with_space(first_page, &first, AuditLogPage::LEN);
+    verify(&mut ledger, 0, &[first_page, second_page]).unwrap();
+}
+
+#[test]
+fn test_slash_executor_instruction_takes_stake_on_evidence() {
+    use arcium_encrypted_compute::{
+        accounts, instruction, ComputationRequest, ComputationStatus, ErrorCode, ExecutorNode, ExecutorRegistry,
+        ResultCircuit, SlashReason, VerifyingKey, EXECUTOR_INITIAL_REPUTATION,
+    };
+
+    let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &arcium_encrypted_compute::ID);
+    let mut ledger = TestLedger::new();
+    let treasury = Pubkey::new_unique();
+    let cranker = Pubkey::new_unique();
+    ledger.fund(treasury, 1_000_000);
+    ledger.fund(cranker, 1_000_000_000);
+
+    let (registry, bump) = pda(&[b"executor_registry"]);
+    ledger.put(registry, &ExecutorRegistry {
+        authority: Pubkey::new_unique(),
+        stake_mint: None,
+        min_stake: 100_000,
+        treasury,
+        created_at: 0,
+        bump,
+    });
+    let executor = Pubkey::new_unique();
+    let (executor_node, bump) = pda(&[b"executor_node", executor.as_ref()]);
+    ledger.put(executor_node, &ExecutorNode {
+        authority: executor,
+        stake: 1_000_000,
+        reputation: EXECUTOR_INITIAL_REPUTATION,
+        active: true,
+        results_submitted: 1,
+        times_slashed: 0,
+        registered_at: 0,
+        deregistered_at: 0,
+        bump,
+    });
+    ledger.accounts.get_mut(&executor_node).unwrap().lamports += 1_000_000;
+
+    let mut definition = sample_computation_definition();
+    definition.spec.result_circuit = Some(ResultCircuit { circuit_id: "private_sum_result".to_string(), key_version: 1 });
+    let (definition_key, bump) = pda(&[b"computation_definition", definition.definition_id.as_bytes(), &definition.version.to_le_bytes()]);
+    definition.bump = bump;
+    ledger.put(definition_key, &definition);
+
+    let encrypted_compute = Pubkey::new_unique();
+    let (computation_request, bump) = pda(&[b"computation_request", encrypted_compute.as_ref(), &0u32.to_le_bytes()]);
+    let mut completed = ComputationRequest {
+        encrypted_compute,
+        status: ComputationStatus::Completed,
+        executor: Some(executor),
+        encrypted_outputs: vec![vec![1, 2, 3]],
+        compute_cost: 10,
+        timeout_at: 2_000,
+        completed_at: 500,
+        bump,
+        ..sample_computation_request()
+    };
+    let (key, proof) = degenerate_groth16_fixture(&[completed.result_commitment()]);
+    let (verifying_key, bump) = pda(&[b"verifying_key", b"private_sum_result", &1u32.to_le_bytes()]);
+    ledger.put(verifying_key, &VerifyingKey {
+        circuit_id: "private_sum_result".to_string(),
+        version: 1,
+        key,
+        superseded: false,
+        deprecated: false,
+        registered_at: 0,
+        deprecated_at: 0,
+        bump,
+    });
+
+    let slash = |ledger: &mut TestLedger, computation_request, reason| ledger.process(
+        accounts::SlashExecutor {
+            registry,
+            executor_node,
+            computation_request,
+            definition: definition_key,
+            verifying_key: Some(verifying_key),
+            stake_vault: None,
+            treasury: Some(treasury),
+            treasury_token_account: None,
+            user: cranker,
+            token_program: None,
+        },
+        instruction::SlashExecutor { reason },
+    );
+    let no_evidence = Err(program_error(ErrorCode::NoSlashingEvidence));
+
+    // A result whose proof verifies is no evidence, whoever cranks the slash
+    completed.result_proof = Some(proof);
+    ledger.put(computation_request, &completed);
+    assert_eq!(slash(&mut ledger, computation_request, SlashReason::InvalidResult), no_evidence);
+
+    // One that fails costs half the stake, once
+    let mut tampered = proof;
+    tampered[..64].copy_from_slice(&g1_generator());
+    completed.result_proof = Some(tampered);
+    ledger.put(computation_request, &completed);
+    slash(&mut ledger, computation_request, SlashReason::InvalidResult).unwrap();
+    assert_eq!(ledger.get::<ExecutorNode>(&executor_node).stake, 500_000);
+    assert_eq!(ledger.accounts[&treasury].lamports, 1_500_000);
+    assert!(ledger.get::<ComputationRequest>(&computation_request).slashed);
+    assert_eq!(slash(&mut ledger, computation_request, SlashReason::InvalidResult), no_evidence);
+
+    // A claimed request only counts against its claimant once it has timed out
+    let (missed_request, bump) = pda(&[b"computation_request", encrypted_compute.as_ref(), &1u32.to_le_bytes()]);
+    let mut missed = completed.clone();
+    missed.request_id = 1;
+    missed.status = ComputationStatus::Queued;
+    missed.assigned_executor = Some(executor);
+    missed.executor = None;
+    missed.encrypted_outputs = Vec::new();
+    missed.result_proof = None;
+    missed.completed_at = 0;
+    missed.slashed = false;
+    missed.bump = bump;
+    ledger.put(missed_request, &missed);
+    assert_eq!(slash(&mut ledger, missed_request, SlashReason::MissedDeadline), no_evidence);
+
+    ledger.unix_timestamp = missed.timeout_at;
+    assert_eq!(slash(&mut ledger, missed_request, SlashReason::InvalidResult), no_evidence);
+    slash(&mut ledger, missed_request, SlashReason::MissedDeadline).unwrap();
+    let node = ledger.get::<ExecutorNode>(&executor_node);
+    assert_eq!((node.stake, node.times_slashed), (450_000, 2));
+    assert_eq!(ledger.accounts[&treasury].lamports, 1_550_000);
+}
+
+#[test]
+fn test_slashing_spl_stake_pays_a_token_account_the_treasury_owns() {
+    use arcium_encrypted_compute::{
+        accounts, instruction, ComputationRequest, ComputationStatus, ErrorCode, ExecutorNode, ExecutorRegistry,
+        SlashReason, EXECUTOR_INITIAL_REPUTATION,
+    };
+
+    let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &arcium_encrypted_compute::ID);
+    let mut ledger = TestLedger::new();
+    let stake_mint = Pubkey::new_unique();
+    let treasury = Pubkey::new_unique();
+    let cranker = Pubkey::new_unique();
+    ledger.fund(cranker, 1_000_000_000);
+
+    let (registry, bump) = pda(&[b"executor_registry"]);
+    ledger.put(registry, &ExecutorRegistry {
+        authority: Pubkey::new_unique(),
+        stake_mint: Some(stake_mint),
+        min_stake: 100_000,
+        treasury,
+        created_at: 0,
+        bump,
+    });
+    let (stake_vault, _) = pda(&[b"executor_stake_vault"]);
+    ledger.put_token_account(stake_vault, stake_mint, registry, 1_000_000);
+
+    let executor = Pubkey::new_unique();
+    let (executor_node, bump) = pda(&[b"executor_node", executor.as_ref()]);
+    ledger.put(executor_node, &ExecutorNode {
+        authority: executor,
+        stake: 1_000_000,
+        reputation: EXECUTOR_INITIAL_REPUTATION,
+        active: true,
+        results_submitted: 0,
+        times_slashed: 0,
+        registered_at: 0,
+        deregistered_at: 0,
+        bump,
+    });
+
+    let definition = sample_computation_definition();
+    let (definition_key, bump) = pda(&[b"computation_definition", definition.definition_id.as_bytes(), &definition.version.to_le_bytes()]);
+    ledger.put(definition_key, &arcium_encrypted_compute::ComputationDefinition { bump, ..definition });
+
+    // A request the node claimed and let time out
+    let encrypted_compute = Pubkey::new_unique();
+    let (computation_request, bump) = pda(&[b"computation_request", encrypted_compute.as_ref(), &0u32.to_le_bytes()]);
+    let missed = ComputationRequest {
+        encrypted_compute,
+        status: ComputationStatus::Queued,
+        assigned_executor: Some(executor),
+        timeout_at: 2_000,
+        bump,
+        ..sample_computation_request()
+    };
+    ledger.put(computation_request, &missed);
+    ledger.unix_timestamp = missed.timeout_at;
+
+    let slash = |stake_vault: Option<Pubkey>, treasury_token_account: Option<Pubkey>| accounts::SlashExecutor {
+        registry,
+        executor_node,
+        computation_request,
+        definition: definition_key,
+        verifying_key: None,
+        stake_vault,
+        treasury: None,
+        treasury_token_account,
+        user: cranker,
+        token_program: Some(anchor_spl::token::ID),
+    };
+    let missed_deadline = || instruction::SlashExecutor { reason: SlashReason::MissedDeadline };
+
+    // The slashed stake goes from the vault to a token account of the stake mint that the
+    // registry's treasury owns, which lives at its own address rather than the treasury's
+    let treasury_tokens = Pubkey::new_unique();
+    ledger.put_token_account(treasury_tokens, stake_mint, treasury, 0);
+    ledger.process_to_cpi(slash(Some(stake_vault), Some(treasury_tokens)), missed_deadline()).unwrap();
+
+    // Not to one somebody else owns, or one holding another mint
+    let stranger_tokens = Pubkey::new_unique();
+    ledger.put_token_account(stranger_tokens, stake_mint, Pubkey::new_unique(), 0);
+    assert_eq!(
+        ledger.process_to_cpi(slash(Some(stake_vault), Some(stranger_tokens)), missed_deadline()),
+        Err(anchor_lang::error::Error::from(anchor_lang::error::ErrorCode::ConstraintTokenOwner).into())
+    );
+    let other_mint_tokens = Pubkey::new_unique();
+    ledger.put_token_account(other_mint_tokens, Pubkey::new_unique(), treasury, 0);
+    assert_eq!(
+        ledger.process_to_cpi(slash(Some(stake_vault), Some(other_mint_tokens)), missed_deadline()),
+        Err(program_error(ErrorCode::InvalidStakeAccounts))
+    );
+
+    // And not without the vault and the treasury's token account
+    assert_eq!(
+        ledger.process_to_cpi(slash(None, Some(treasury_tokens)), missed_deadline()),
+        Err(program_error(ErrorCode::InvalidStakeAccounts))
+    );
+    assert_eq!(
+        ledger.process_to_cpi(slash(Some(stake_vault), None), missed_deadline()),
+        Err(program_error(ErrorCode::InvalidStakeAccounts))
+    );
+}
+
+struct QueuedComputation {
+    encrypted_compute: Pubkey,
+    computation_request: Pubkey,
+    definition: Pubkey,
+    fee_escrow: Pubkey,
+    executor_registry: Pubkey,
+    executor_node: Pubkey,
+    executor: Pubkey,
+    requester: Pubkey,
+    treasury: Pubkey,
+}
+
+// A ledger holding a request queued on the sample account with a 10_000 fee in escrow
+// (5% to the treasury), timing out at 2_000, and an executor able to complete it
+fn ledger_with_queued_computation(fee_mint: Option<Pubkey>) -> (TestLedger, QueuedComputation) {
+    use arcium_encrypted_compute::{ComputationRequest, ExecutorNode, ExecutorRegistry, FeeEscrow, EXECUTOR_INITIAL_REPUTATION};
+
+    let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &arcium_encrypted_compute::ID);
+    let (mut ledger, encrypted_compute) = ledger_with(&sample_encrypted_compute());
+    let executor = Pubkey::new_unique();
+    let requester = Pubkey::new_unique();
+    let treasury = Pubkey::new_unique();
+    for wallet in [executor, requester, treasury] {
+        ledger.fund(wallet, 1_000_000_000);
+    }
+
+    let mut definition = sample_computation_definition();
+    let (definition_key, bump) = pda(&[b"computation_definition", definition.definition_id.as_bytes(), &definition.version.to_le_bytes()]);
+    definition.bump = bump;
+    ledger.put(definition_key, &definition);
+
+    let (computation_request, bump) = pda(&[b"computation_request", encrypted_compute.as_ref(), &0u32.to_le_bytes()]);
+    let request = ComputationRequest {
+        encrypted_compute,
+        requester,
+        fee: 10_000,
+        fee_mint,
+        queued_at: 900,
+        timeout_at: 2_000,
+        bump,
+        ..sample_computation_request()
+    };
+    ledger.put_with_space(computation_request, &request, ComputationRequest::LEN);
+    let (fee_escrow, bump) = pda(&[b"fee_escrow", computation_request.as_ref()]);
+    ledger.put(fee_escrow, &FeeEscrow {
+        request: computation_request,
+        mint: fee_mint,
+        amount: 10_000,
+        protocol_fee_bps: 500,
+        treasury,
+        bump,
+    });
+    // SOL fees sit on the escrow on top of its rent
+    if fee_mint.is_none() {
+        ledger.accounts.get_mut(&fee_escrow).unwrap().lamports += 10_000;
+    }
+
+    let (executor_registry, bump) = pda(&[b"executor_registry"]);
+    ledger.put(executor_registry, &ExecutorRegistry {
+        authority: Pubkey::new_unique(),
+        stake_mint: None,
+        min_stake: 1_000,
+        treasury: Pubkey::new_unique(),
+        created_at: 0,
+        bump,
+    });
+    let (executor_node, bump) = pda(&[b"executor_node", executor.as_ref()]);
+    ledger.put(executor_node, &ExecutorNode {
+        authority: executor,
+        stake: 1_000,
+        reputation: EXECUTOR_INITIAL_REPUTATION,
+        active: true,
+        results_submitted: 0,
+        times_slashed: 0,
+        registered_at: 0,
+        deregistered_at: 0,
+        bump,
+    });
+
+    (ledger, QueuedComputation {
+        encrypted_compute,
+        computation_request,
+        definition: definition_key,
+        fee_escrow,
+        executor_registry,
+        executor_node,
+        executor,
+        requester,
+        treasury,
+    })
+}
+
+impl QueuedComputation {
+    fn submit(&self, ledger: &mut TestLedger, treasury: Option<Pubkey>) -> std::result::Result<(), ProgramError> {
+        use arcium_encrypted_compute::{accounts, instruction};
+
+        ledger.process(
+            accounts::SubmitComputationResult {
+                encrypted_compute: self.encrypted_compute,
+                computation_request: self.computation_request,
+                definition: self.definition,
+                executor_registry: self.executor_registry,
+                executor_node: self.executor_node,
+                fee_escrow: self.fee_escrow,
+                treasury,
+                fee_vault: None,
+                executor_token_account: None,
+                treasury_token_account: None,
+                executor: self.executor,
+                token_program: None,
+            },
+            instruction::SubmitComputationResult {
+                encrypted_outputs: vec![vec![1, 2, 3]],
+                compute_cost: 10,
+                result_proof: None,
+            },
+        )
+    }
+
+    fn refund(&self, ledger: &mut TestLedger, user: Pubkey) -> std::result::Result<(), ProgramError> {
+        use arcium_encrypted_compute::{accounts, instruction};
+
+        ledger.process(
+            accounts::RefundTimedOutComputation {
+                computation_request: self.computation_request,
+                fee_escrow: self.fee_escrow,
+                requester: self.requester,
+                fee_vault: None,
+                requester_token_account: None,
+                user,
+                token_program: None,
+            },
+            instruction::RefundTimedOutComputation {},
+        )
+    }
+}
+
+#[test]
+fn test_completion_splits_sol_fee_between_executor_and_treasury() {
+    use arcium_encrypted_compute::{
+        ComputationRequest, ComputationStatus, EncryptedCompute, EncryptedComputeStatus, ErrorCode, FeeEscrow,
+    };
+
+    let (mut ledger, queued) = ledger_with_queued_computation(None);
+    let escrow_rent = Rent::default().minimum_balance(ledger.accounts[&queued.fee_escrow].data.len());
+
+    // A protocol cut can't be paid without the treasury
+    assert_eq!(queued.submit(&mut ledger, None), Err(program_error(ErrorCode::InvalidFeeAccounts)));
+
+    queued.submit(&mut ledger, Some(queued.treasury)).unwrap();
+    assert_eq!(ledger.accounts[&queued.executor].lamports, 1_000_000_000 + 9_500);
+    assert_eq!(ledger.accounts[&queued.treasury].lamports, 1_000_000_000 + 500);
+    assert_eq!(ledger.accounts[&queued.fee_escrow].lamports, escrow_rent);
+    assert_eq!(ledger.get::<FeeEscrow>(&queued.fee_escrow).amount, 0);
+
+    let request = ledger.get::<ComputationRequest>(&queued.computation_request);
+    assert_eq!((request.status, request.executor), (ComputationStatus::Completed, Some(queued.executor)));
+    let encrypted_compute = ledger.get::<EncryptedCompute>(&queued.encrypted_compute);
+    assert_eq!(encrypted_compute.status, EncryptedComputeStatus::Processed);
+    assert_eq!(encrypted_compute.computations_completed, 1);
+}
+
+#[test]
+fn test_timed_out_sol_fee_is_refunded_to_the_requester() {
+    use arcium_encrypted_compute::ErrorCode;
+
+    let (mut ledger, queued) = ledger_with_queued_computation(None);
+    let cranker = Pubkey::new_unique();
+    ledger.fund(cranker, 1_000_000_000);
+    let held = ledger.accounts[&queued.computation_request].lamports + ledger.accounts[&queued.fee_escrow].lamports;
+
+    assert_eq!(queued.refund(&mut ledger, cranker), Err(program_error(ErrorCode::ComputationNotTimedOut)));
+
+    // Anyone may crank the refund; the fee and both accounts' rent go to the requester
+    ledger.unix_timestamp = 2_000;
+    assert_eq!(queued.submit(&mut ledger, Some(queued.treasury)), Err(program_error(ErrorCode::ComputationTimedOut)));
+    queued.refund(&mut ledger, cranker).unwrap();
+    assert!(!ledger.accounts.contains_key(&queued.computation_request));
+    assert!(!ledger.accounts.contains_key(&queued.fee_escrow));
+    assert_eq!(ledger.accounts[&queued.requester].lamports, 1_000_000_000 + held);
+    assert_eq!(ledger.accounts[&queued.treasury].lamports, 1_000_000_000);
+}
+
+#[test]
+fn test_fee_accounts_must_match_the_escrow_currency() {
+    use arcium_encrypted_compute::{accounts, instruction, ComputationRequest, ComputationStatus, ErrorCode, FeeConfig};
+
+    // Only whitelisted mints are accepted, and only with a vault to hold the fee
+    let usdc = Pubkey::new_unique();
+    let fee_config = FeeConfig {
+        authority: Pubkey::new_unique(),
+        treasury: Pubkey::new_unique(),
+        protocol_fee_bps: 500,
+        allowed_mints: vec![usdc],
+        bump: 0,
+    };
+    fee_config.require_fee_accounts(None, false).unwrap();
+    fee_config.require_fee_accounts(Some(&usdc), true).unwrap();
+    assert_eq!(
+        fee_config.require_fee_accounts(Some(&Pubkey::new_unique()), true),
+        Err(ErrorCode::FeeMintNotAllowed.into())
+    );
+    assert_eq!(
+        fee_config.require_fee_accounts(Some(&usdc), false),
+        Err(ErrorCode::InvalidFeeAccounts.into())
+    );
+
+    // An SPL escrow can't be paid out, refunded or closed without its token accounts
+    let (mut ledger, queued) = ledger_with_queued_computation(Some(usdc));
+    let invalid_fee_accounts = Err(program_error(ErrorCode::InvalidFeeAccounts));
+    assert_eq!(queued.submit(&mut ledger, Some(queued.treasury)), invalid_fee_accounts);
+
+    ledger.unix_timestamp = 2_000;
+    assert_eq!(queued.refund(&mut ledger, queued.requester), invalid_fee_accounts);
+
+    let mut completed = ledger.get::<ComputationRequest>(&queued.computation_request);
+    completed.status = ComputationStatus::Completed;
+    completed.executor = Some(queued.executor);
+    ledger.put(queued.computation_request, &completed);
+    assert_eq!(
+        ledger.process(
+            accounts::CloseComputationRequest {
+                computation_request: queued.computation_request,
+                fee_escrow: queued.fee_escrow,
+                fee_vault: None,
+                requester_token_account: None,
+                user: queued.requester,
+                token_program: None,
+            },
+            instruction::CloseComputationRequest {},
+        ),
+        invalid_fee_accounts
+    );
+}
@@ -0,0 +1,215 @@
+// In-process runtime for program-level tests. Instructions go through the program's
+// entrypoint with their accounts laid out the way the runtime serializes them, so
+// Anchor's account constraints, reallocation and closing run as they do on chain.
+// Anchor's CPI helpers only exist on-chain, so instructions that create accounts or
+// move tokens can't run here; tests write the accounts they need directly instead, and
+// run token-moving instructions as far as their transfer with `process_to_cpi`.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::{deserialize, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER};
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_spl::token::spl_token;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use solana_sysvar::program_stubs::{set_syscall_stubs, SyscallStubs};
+
+thread_local! {
+    static LEDGER_CLOCK: Cell<(u64, i64)> = const { Cell::new((0, 0)) };
+}
+
+struct TestSyscalls;
+
+impl SyscallStubs for TestSyscalls {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let (slot, unix_timestamp) = LEDGER_CLOCK.with(Cell::get);
+        let clock = Clock { slot, unix_timestamp, ..Clock::default() };
+        unsafe { std::ptr::write(var_addr as *mut Clock, clock) };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { std::ptr::write(var_addr as *mut Rent, Rent::default()) };
+        0
+    }
+}
+
+#[derive(Clone)]
+pub struct LedgerAccount {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+pub struct TestLedger {
+    pub accounts: HashMap<Pubkey, LedgerAccount>,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+impl TestLedger {
+    pub fn new() -> Self {
+        static INSTALL_SYSCALLS: std::sync::Once = std::sync::Once::new();
+        INSTALL_SYSCALLS.call_once(|| {
+            set_syscall_stubs(Box::new(TestSyscalls));
+        });
+
+        let mut ledger = TestLedger { accounts: HashMap::new(), slot: 1, unix_timestamp: 1_000 };
+        for program in [arcium_encrypted_compute::ID, anchor_lang::system_program::ID, anchor_spl::token::ID] {
+            ledger.accounts.insert(program, LedgerAccount {
+                lamports: 1,
+                data: Vec::new(),
+                owner: anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+                executable: true,
+            });
+        }
+        ledger
+    }
+
+    // A fresh system account holding `lamports`
+    pub fn fund(&mut self, key: Pubkey, lamports: u64) {
+        self.accounts.insert(key, LedgerAccount {
+            lamports,
+            data: Vec::new(),
+            owner: anchor_lang::system_program::ID,
+            executable: false,
+        });
+    }
+
+    // Store an account of this program, rent-exempt at its serialized size
+    pub fn put<T: AccountSerialize>(&mut self, key: Pubkey, account: &T) {
+        self.put_with_space(key, account, 0);
+    }
+
+    // Like `put`, but zero-padded to `space` bytes the way `init` allocates accounts
+    pub fn put_with_space<T: AccountSerialize>(&mut self, key: Pubkey, account: &T, space: usize) {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data.resize(data.len().max(space), 0);
+        self.accounts.insert(key, LedgerAccount {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: arcium_encrypted_compute::ID,
+            executable: false,
+        });
+    }
+
+    // An initialized SPL token account of `mint` held by `owner`
+    pub fn put_token_account(&mut self, key: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) {
+        let account = spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        account.pack_into_slice(&mut data);
+        self.accounts.insert(key, LedgerAccount {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: anchor_spl::token::ID,
+            executable: false,
+        });
+    }
+
+    pub fn get<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        T::try_deserialize(&mut self.accounts[key].data.as_slice()).unwrap()
+    }
+
+    pub fn process(&mut self, accounts: impl ToAccountMetas, args: impl InstructionData) -> std::result::Result<(), ProgramError> {
+        self.process_with_remaining(accounts, Vec::new(), args)
+    }
+
+    // Run one instruction; like a failed transaction, an error leaves every account untouched
+    pub fn process_with_remaining(
+        &mut self,
+        accounts: impl ToAccountMetas,
+        remaining_accounts: Vec<AccountMeta>,
+        args: impl InstructionData,
+    ) -> std::result::Result<(), ProgramError> {
+        let mut metas = accounts.to_account_metas(None);
+        metas.extend(remaining_accounts);
+        let data = args.data();
+
+        let mut input = (metas.len() as u64).to_le_bytes().to_vec();
+        for (index, meta) in metas.iter().enumerate() {
+            if let Some(original) = metas[..index].iter().position(|earlier| earlier.pubkey == meta.pubkey) {
+                input.push(original as u8);
+                input.extend([0u8; 7]);
+                continue;
+            }
+            let account = self.accounts.get(&meta.pubkey).cloned().unwrap_or(LedgerAccount {
+                lamports: 0,
+                data: Vec::new(),
+                owner: anchor_lang::system_program::ID,
+                executable: false,
+            });
+            // The runtime merges the privileges of every meta for the same account
+            let is_signer = metas.iter().any(|other| other.pubkey == meta.pubkey && other.is_signer);
+            let is_writable = metas.iter().any(|other| other.pubkey == meta.pubkey && other.is_writable);
+            input.extend([NON_DUP_MARKER, is_signer as u8, is_writable as u8, account.executable as u8]);
+            input.extend([0u8; 4]);
+            input.extend(meta.pubkey.to_bytes());
+            input.extend(account.owner.to_bytes());
+            input.extend(account.lamports.to_le_bytes());
+            input.extend((account.data.len() as u64).to_le_bytes());
+            input.extend(&account.data);
+            input.resize((input.len() + MAX_PERMITTED_DATA_INCREASE).next_multiple_of(8), 0);
+            input.extend(u64::MAX.to_le_bytes());
+        }
+        input.extend((data.len() as u64).to_le_bytes());
+        input.extend(&data);
+        input.extend(arcium_encrypted_compute::ID.to_bytes());
+
+        // The entrypoint reads the buffer as 8-byte aligned words
+        let mut buffer = vec![0u64; input.len().div_ceil(8)];
+        unsafe { std::ptr::copy_nonoverlapping(input.as_ptr(), buffer.as_mut_ptr() as *mut u8, input.len()) };
+
+        LEDGER_CLOCK.with(|clock| clock.set((self.slot, self.unix_timestamp)));
+        let (program_id, infos, instruction_data) = unsafe { deserialize(buffer.as_mut_ptr() as *mut u8) };
+        arcium_encrypted_compute::entry(program_id, &infos, instruction_data)?;
+
+        for info in infos.iter().filter(|info| info.is_writable) {
+            if info.lamports() == 0 {
+                self.accounts.remove(info.key);
+                continue;
+            }
+            self.accounts.insert(*info.key, LedgerAccount {
+                lamports: info.lamports(),
+                data: info.data.borrow().to_vec(),
+                owner: *info.owner,
+                executable: info.executable,
+            });
+        }
+        Ok(())
+    }
+
+    // Run an instruction that ends in a CPI up to that CPI. Ok means every check before it
+    // passed; the ledger is left untouched either way.
+    pub fn process_to_cpi(
+        &self,
+        accounts: impl ToAccountMetas,
+        args: impl InstructionData,
+    ) -> std::result::Result<(), ProgramError> {
+        let mut scratch = TestLedger { accounts: self.accounts.clone(), ..*self };
+        match catch_unwind(AssertUnwindSafe(|| scratch.process(accounts, args))) {
+            Ok(Ok(())) => panic!("instruction finished without making a CPI"),
+            Ok(Err(error)) => Err(error),
+            Err(payload) => {
+                let message = payload.downcast_ref::<&str>().copied()
+                    .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                    .unwrap_or_default();
+                assert!(message.contains("target_os = \"solana\""), "instruction panicked: {message}");
+                Ok(())
+            }
+        }
+    }
+}
+
+pub fn program_error(code: arcium_encrypted_compute::ErrorCode) -> ProgramError {
+    anchor_lang::error::Error::from(code).into()
+}